@@ -1 +1,17 @@
+// The interpreter is written with explicit `return`s, `self: &Self` receivers and
+// exhaustive `match`es on purpose; keep clippy from fighting that style.
+#![allow(
+    clippy::needless_return,
+    clippy::needless_arbitrary_self_type,
+    clippy::redundant_field_names,
+    clippy::useless_format,
+    clippy::single_match,
+    clippy::ptr_arg,
+    clippy::to_string_in_format_args,
+    clippy::match_like_matches_macro,
+    clippy::collapsible_else_if,
+    clippy::collapsible_match
+)]
 pub mod noa;
+#[cfg(feature = "serde")]
+pub use noa::{ValueError, from_value, to_value};
@@ -6,10 +6,15 @@ use noa::noa::Noa;
 struct Args {
     #[arg()]
     script: String,
+    /// Arguments passed to the script as `args`
+    #[arg(trailing_var_arg = true, allow_hyphen_values = true)]
+    args: Vec<String>,
 }
 fn main() {
     let args = Args::parse();
     let mut noa = Noa::new();
+    noa.set_fs_root(".");
+    noa.set_args(args.args);
     noa.load_libray();
     if args.script.is_empty() {
         eprintln!("Script was not provided as argument");
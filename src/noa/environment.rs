@@ -1,8 +1,10 @@
 use std::{
-    collections::HashMap,
+    collections::HashSet,
     sync::{Arc, Mutex},
 };
 
+use indexmap::IndexMap;
+
 use crate::noa::{
     error::{NoaError, NoaTermination},
     token::Token,
@@ -10,15 +12,19 @@ use crate::noa::{
 };
 #[derive(Debug, Clone)]
 pub struct Environment {
-    values: HashMap<String, Object>,
+    /// Values in the order their names were first defined.
+    values: IndexMap<String, Object>,
+    /// Names declared with `const` in this scope.
+    constants: HashSet<String>,
     enclosing: Option<Arc<Mutex<Environment>>>,
 }
 
 impl Environment {
     pub fn new() -> Self {
-        let values: HashMap<String, Object> = HashMap::new();
+        let values: IndexMap<String, Object> = IndexMap::new();
         return Environment {
             values: values,
+            constants: HashSet::new(),
             enclosing: None,
         };
     }
@@ -29,6 +35,7 @@ impl Environment {
                     let enclose = mutex.snapshot()?;
                     return Ok(Self {
                         values: self.values.clone(),
+                        constants: self.constants.clone(),
                         enclosing: Some(Arc::new(Mutex::new(enclose))),
                     });
                 }
@@ -44,15 +51,47 @@ impl Environment {
         };
         return Ok(Self {
             values: self.values.clone(),
+            constants: self.constants.clone(),
             enclosing: None,
         });
     }
+    pub fn values(self: &Self) -> &IndexMap<String, Object> {
+        return &self.values;
+    }
     pub fn enclose(self: &mut Self, enclosing: Arc<Mutex<Environment>>) {
         self.enclosing = Some(enclosing);
     }
     pub fn define(self: &mut Self, identifier: String, value: Object) {
+        self.constants.remove(&identifier);
         self.values.insert(identifier, value);
     }
+    /// Defines a variable that `assign` refuses to change.
+    pub fn define_constant(self: &mut Self, identifier: String, value: Object) {
+        self.values.insert(identifier.clone(), value);
+        self.constants.insert(identifier);
+    }
+    /// Defines a `let`, `const` or `fn` name declared in the script. Constants can't be
+    /// redeclared in the scope that holds them.
+    pub fn declare(
+        self: &mut Self,
+        token: &Token,
+        value: Object,
+        constant: bool,
+    ) -> Result<(), NoaTermination> {
+        if self.constants.contains(&token.lexeme) {
+            return Err(NoaTermination::Error(NoaError {
+                line: token.line,
+                location: token.lexeme.clone(),
+                message: format!("Cannot redeclare constant {}", token.lexeme),
+            }));
+        }
+        if constant {
+            self.define_constant(token.lexeme.clone(), value);
+        } else {
+            self.define(token.lexeme.clone(), value);
+        }
+        Ok(())
+    }
     pub fn assign(self: &mut Self, token: &Token, value: Object) -> Result<(), NoaTermination> {
         if !self.values.contains_key(&token.lexeme) {
             match &self.enclosing {
@@ -71,6 +110,13 @@ impl Environment {
                 }
             }
         }
+        if self.constants.contains(&token.lexeme) {
+            return Err(NoaTermination::Error(NoaError {
+                line: token.line,
+                location: token.lexeme.clone(),
+                message: format!("Cannot assign to constant {}", token.lexeme),
+            }));
+        }
         self.values.insert(token.lexeme.clone(), value);
         Ok(())
     }
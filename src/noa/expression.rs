@@ -1,11 +1,10 @@
-use std::{
-    collections::HashMap,
-    sync::{Arc, Mutex},
-};
+use std::sync::{Arc, Mutex, MutexGuard};
 
 use crate::noa::{
     environment::Environment,
     error::{NoaError, NoaTermination},
+    pattern::Pattern,
+    statement::Statement,
     token::{Token, TokenType},
     types::{Number, Object, Table},
 };
@@ -23,7 +22,24 @@ pub enum Expression {
     Logical(LogicalExpression),
     Call(CallExpression),
     Table(TableExpression),
+    List(ListExpression),
+    Template(TemplateExpression),
     KeyAccess(KeyAccessExpression),
+    Match(MatchExpression),
+}
+
+enum Operands {
+    Int(i64, i64),
+    Float(Number, Number),
+}
+
+impl Operands {
+    fn is_zero_divisor(self: &Self) -> bool {
+        match self {
+            Operands::Int(_, n2) => return *n2 == 0,
+            Operands::Float(_, n2) => return *n2 == 0.0,
+        }
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -31,11 +47,49 @@ pub struct KeyAccessExpression {
     pub target: Box<Expression>,
     pub left_bracket: Token,
     pub key: Box<Expression>,
+    /// `a?["k"]` and `a?.k` evaluate to nil when `a` is nil.
+    pub optional: bool,
+}
+
+/// `match (value) { pattern, pattern => body, ... }`. The first arm with a matching
+/// pattern runs, and nil is the result when none does.
+#[derive(Debug, Clone)]
+pub struct MatchExpression {
+    pub keyword: Token,
+    pub value: Box<Expression>,
+    pub arms: Vec<MatchArm>,
+}
+
+#[derive(Debug, Clone)]
+pub struct MatchArm {
+    /// Alternatives, the arm runs when any of them matches.
+    pub patterns: Vec<Pattern>,
+    pub body: MatchBody,
+}
+
+#[derive(Debug, Clone)]
+pub enum MatchBody {
+    /// The arm's value is the expression's value.
+    Expression(Box<Expression>),
+    /// A block runs its statements, and the arm's value is nil.
+    Block(Box<Statement>),
 }
 
 #[derive(Debug, Clone)]
 pub struct TableExpression {
-    pub values: HashMap<String, Expression>,
+    /// Entries in source order, which is also the order they are evaluated in.
+    pub values: Vec<(String, Expression)>,
+}
+
+#[derive(Debug, Clone)]
+pub struct ListExpression {
+    pub values: Vec<Expression>,
+}
+
+/// A string with embedded `${...}` expressions. The parts are stringified and joined.
+#[derive(Debug, Clone)]
+pub struct TemplateExpression {
+    pub parts: Vec<Expression>,
 }
 
 #[derive(Debug, Clone)]
@@ -43,6 +97,8 @@ pub struct CallExpression {
     pub callee: Box<Expression>,
     pub paren: Token,
     pub arguments: Vec<Expression>,
+    /// `f?.()` evaluates to nil, without evaluating the arguments, when `f` is nil.
+    pub optional: bool,
 }
 
 #[derive(Debug, Clone)]
@@ -56,12 +112,19 @@ pub struct LogicalExpression {
 pub struct AssginExpression {
     pub token: Token,
     pub expression: Box<Expression>,
+    /// Operator applied to the current value and `expression`, for `x += 1` and `x++`.
+    pub operator: Option<Token>,
+    /// `x++` and `x--` evaluate to the value from before the update.
+    pub postfix: bool,
 }
 
 #[derive(Debug, Clone)]
 pub struct KeyAccessAssignExpression {
     pub key_access: Box<Expression>,
     pub expression: Box<Expression>,
+    /// Same as `AssginExpression::operator`. The target and key are evaluated once.
+    pub operator: Option<Token>,
+    pub postfix: bool,
 }
 
 #[derive(Debug, Clone)]
@@ -104,7 +167,7 @@ impl Expression {
         let mut res: String = String::new();
 
         res.push('(');
-        res.push_str(&name);
+        res.push_str(name);
 
         for expr in expressions {
             res.push(' ');
@@ -121,6 +184,9 @@ impl Expression {
             Object::Number(n) => {
                 return Ok(n);
             }
+            Object::Int(n) => {
+                return Ok(n as Number);
+            }
             _ => {
                 return Err(NoaTermination::Error(NoaError {
                     line: line,
@@ -131,106 +197,394 @@ impl Expression {
         }
     }
 
-    pub fn evaluate(
-        self: &Self,
-        environment: Arc<Mutex<Environment>>,
+    /// Arithmetic on two ints stays exact, anything involving a float is done in floats.
+    fn get_operands(left: Object, right: Object, line: usize) -> Result<Operands, NoaTermination> {
+        if let (Object::Int(n1), Object::Int(n2)) = (&left, &right) {
+            return Ok(Operands::Int(*n1, *n2));
+        }
+        let n1 = Self::get_number_object(left, line)?;
+        let n2 = Self::get_number_object(right, line)?;
+        return Ok(Operands::Float(n1, n2));
+    }
+
+    /// Turns the result of a checked int operation into an object, `None` meaning overflow.
+    fn checked(result: Option<i64>, operator: &Token) -> Result<Object, NoaTermination> {
+        match result {
+            Some(n) => return Ok(Object::Int(n)),
+            None => {
+                return Err(NoaTermination::Error(NoaError {
+                    line: operator.line,
+                    location: operator.lexeme.clone(),
+                    message: format!("Integer overflow"),
+                }));
+            }
+        }
+    }
+
+    /// Bitwise operators work on integers. Floats holding a whole number are accepted too.
+    fn get_bitwise_operand(object: &Object, operator: &Token) -> Result<i64, NoaTermination> {
+        match object {
+            Object::Int(n) => return Ok(*n),
+            Object::Number(n)
+                if n.fract() == 0.0
+                    && (-9223372036854775808.0..9223372036854775808.0).contains(n) =>
+            {
+                return Ok(*n as i64);
+            }
+            _ => {
+                return Err(NoaTermination::Error(NoaError {
+                    line: operator.line,
+                    location: operator.lexeme.clone(),
+                    message: format!(
+                        "Operator {} expects integers, got \"{}\"",
+                        operator.lexeme, object
+                    ),
+                }));
+            }
+        }
+    }
+
+    /// Lists are indexed by non-negative integers. Negative indexes never exist.
+    fn get_list_index(key: &Object, token: &Token) -> Result<Option<usize>, NoaTermination> {
+        match key {
+            Object::Int(n) => {
+                if *n < 0 {
+                    return Ok(None);
+                }
+                return Ok(Some(*n as usize));
+            }
+            Object::Number(n) if n.fract() == 0.0 => {
+                if *n < 0.0 {
+                    return Ok(None);
+                }
+                return Ok(Some(*n as usize));
+            }
+            _ => {
+                return Err(NoaTermination::Error(NoaError {
+                    line: token.line,
+                    location: token.lexeme.clone(),
+                    message: format!("List index must be an integer, got \"{}\"", key),
+                }));
+            }
+        }
+    }
+
+    fn lock<'a>(
+        environment: &'a Arc<Mutex<Environment>>,
+        token: &Token,
+    ) -> Result<MutexGuard<'a, Environment>, NoaTermination> {
+        match environment.lock() {
+            Ok(mutex) => return Ok(mutex),
+            Err(_) => {
+                return Err(NoaTermination::Error(NoaError {
+                    line: token.line,
+                    location: token.lexeme.clone(),
+                    message: format!("Failed to get local scope memory"),
+                }));
+            }
+        }
+    }
+
+    /// Applies a binary operator to two evaluated operands. Compound assignments share it.
+    fn evaluate_binary(
+        left_value: Object,
+        operator: &Token,
+        right_value: Object,
     ) -> Result<Object, NoaTermination> {
-        match self {
-            Expression::Binary(binary_expression) => {
-                let left_value = binary_expression.left.evaluate(environment.clone())?;
-                let right_value = binary_expression.right.evaluate(environment.clone())?;
+        match operator.token_type {
+            // Equality
+            TokenType::Comma => {
+                return Ok(right_value);
+            }
+            // Equality
+            TokenType::EqualEqual => {
+                return Ok(Object::Bool(left_value.is_equal(&right_value)));
+            }
+            TokenType::BangEqual => {
+                return Ok(Object::Bool(!left_value.is_equal(&right_value)));
+            }
+            // Comparison
+            TokenType::Greater => {
+                return Ok(Object::Bool(left_value.is_greater(&right_value)));
+            }
+            TokenType::GreaterEqual => {
+                return Ok(Object::Bool(
+                    left_value.is_greater(&right_value) || left_value.is_equal(&right_value),
+                ));
+            }
+            TokenType::Less => {
+                return Ok(Object::Bool(left_value.is_less(&right_value)));
+            }
+            TokenType::LessEqual => {
+                return Ok(Object::Bool(
+                    left_value.is_less(&right_value) || left_value.is_equal(&right_value),
+                ));
+            }
 
-                match binary_expression.operator.token_type {
-                    // Equality
-                    TokenType::Comma => {
-                        return Ok(right_value);
-                    }
-                    // Equality
-                    TokenType::EqualEqual => {
-                        return Ok(Object::Bool(left_value.is_equal(&right_value)));
-                    }
-                    TokenType::BangEqual => {
-                        return Ok(Object::Bool(!left_value.is_equal(&right_value)));
-                    }
-                    // Comparison
-                    TokenType::Greater => {
-                        return Ok(Object::Bool(left_value.is_greater(&right_value)));
-                    }
-                    TokenType::GreaterEqual => {
-                        return Ok(Object::Bool(
-                            left_value.is_greater(&right_value)
-                                || left_value.is_equal(&right_value),
-                        ));
+            // Term
+            TokenType::Plus => {
+                if let Object::String(str) = left_value {
+                    return Ok(Object::String(str + &right_value.to_string()));
+                }
+                if let Object::String(str) = right_value {
+                    return Ok(Object::String(left_value.to_string() + &str));
+                }
+
+                match Self::get_operands(left_value, right_value, operator.line)? {
+                    Operands::Int(n1, n2) => {
+                        return Self::checked(n1.checked_add(n2), operator);
                     }
-                    TokenType::Less => {
-                        return Ok(Object::Bool(left_value.is_less(&right_value)));
+                    Operands::Float(n1, n2) => return Ok(Object::Number(n1 + n2)),
+                }
+            }
+            TokenType::Minus => match Self::get_operands(left_value, right_value, operator.line)? {
+                Operands::Int(n1, n2) => {
+                    return Self::checked(n1.checked_sub(n2), operator);
+                }
+                Operands::Float(n1, n2) => return Ok(Object::Number(n1 - n2)),
+            },
+            // Factor
+            TokenType::Star => match Self::get_operands(left_value, right_value, operator.line)? {
+                Operands::Int(n1, n2) => {
+                    return Self::checked(n1.checked_mul(n2), operator);
+                }
+                Operands::Float(n1, n2) => return Ok(Object::Number(n1 * n2)),
+            },
+            // Always a float, `7 / 2` is `3.5`
+            TokenType::Slash => {
+                let (n1, n2) = match Self::get_operands(left_value, right_value, operator.line)? {
+                    Operands::Int(n1, n2) => (n1 as Number, n2 as Number),
+                    Operands::Float(n1, n2) => (n1, n2),
+                };
+                if n2 == 0.0 {
+                    return Err(NoaTermination::Error(NoaError {
+                        line: operator.line,
+                        location: n2.to_string(),
+                        message: format!("Cannot divide by zero"),
+                    }));
+                }
+                return Ok(Object::Number(n1 / n2));
+            }
+            TokenType::TildeSlash => {
+                let operands = Self::get_operands(left_value, right_value, operator.line)?;
+                if operands.is_zero_divisor() {
+                    return Err(NoaTermination::Error(NoaError {
+                        line: operator.line,
+                        location: "0".to_owned(),
+                        message: format!("Cannot divide by zero"),
+                    }));
+                }
+                match operands {
+                    Operands::Int(n1, n2) => {
+                        let quotient = n1.checked_div(n2);
+                        // Round towards negative infinity like the float version
+                        let floored = quotient.map(|q| {
+                            if n1 % n2 != 0 && (n1 < 0) != (n2 < 0) {
+                                q - 1
+                            } else {
+                                q
+                            }
+                        });
+                        return Self::checked(floored, operator);
                     }
-                    TokenType::LessEqual => {
-                        return Ok(Object::Bool(
-                            left_value.is_less(&right_value) || left_value.is_equal(&right_value),
-                        ));
+                    Operands::Float(n1, n2) => {
+                        return Ok(Object::Number((n1 / n2).floor()));
                     }
-
-                    // Term
-                    TokenType::Plus => {
-                        if let Object::String(str) = left_value {
-                            return Ok(Object::String(str + &right_value.to_string()));
+                }
+            }
+            // The result takes the sign of the divisor, so `-1 % 3` is `2`
+            TokenType::Percent => {
+                let operands = Self::get_operands(left_value, right_value, operator.line)?;
+                if operands.is_zero_divisor() {
+                    return Err(NoaTermination::Error(NoaError {
+                        line: operator.line,
+                        location: "0".to_owned(),
+                        message: format!("Cannot take the remainder of division by zero"),
+                    }));
+                }
+                match operands {
+                    Operands::Int(n1, n2) => {
+                        // Only i64::MIN % -1 overflows, and its remainder is 0
+                        let remainder = n1.checked_rem(n2).unwrap_or(0);
+                        if remainder != 0 && (remainder < 0) != (n2 < 0) {
+                            return Ok(Object::Int(remainder + n2));
                         }
-                        if let Object::String(str) = right_value {
-                            return Ok(Object::String(left_value.to_string() + &str));
+                        return Ok(Object::Int(remainder));
+                    }
+                    Operands::Float(n1, n2) => {
+                        let remainder = n1 % n2;
+                        if remainder != 0.0 && (remainder < 0.0) != (n2 < 0.0) {
+                            return Ok(Object::Number(remainder + n2));
                         }
-
-                        let n1 =
-                            Self::get_number_object(left_value, binary_expression.operator.line)?;
-
-                        let n2 =
-                            Self::get_number_object(right_value, binary_expression.operator.line)?;
-
-                        return Ok(Object::Number(n1 + n2));
+                        return Ok(Object::Number(remainder));
                     }
-                    TokenType::Minus => {
-                        let n1 =
-                            Self::get_number_object(left_value, binary_expression.operator.line)?;
-
-                        let n2 =
-                            Self::get_number_object(right_value, binary_expression.operator.line)?;
-
-                        return Ok(Object::Number(n1 - n2));
+                }
+            }
+            // Ints raised to a non-negative int stay ints
+            TokenType::StarStar => {
+                match Self::get_operands(left_value, right_value, operator.line)? {
+                    Operands::Int(n1, n2) if n2 >= 0 => {
+                        let result = u32::try_from(n2)
+                            .ok()
+                            .and_then(|exponent| n1.checked_pow(exponent));
+                        return Self::checked(result, operator);
                     }
-                    // Factor
-                    TokenType::Star => {
-                        let n1 =
-                            Self::get_number_object(left_value, binary_expression.operator.line)?;
+                    Operands::Int(n1, n2) => {
+                        return Ok(Object::Number((n1 as Number).powf(n2 as Number)));
+                    }
+                    Operands::Float(n1, n2) => return Ok(Object::Number(n1.powf(n2))),
+                }
+            }
+            // Bitwise
+            TokenType::Ampersand | TokenType::Pipe | TokenType::Caret => {
+                let n1 = Self::get_bitwise_operand(&left_value, operator)?;
+                let n2 = Self::get_bitwise_operand(&right_value, operator)?;
+                match operator.token_type {
+                    TokenType::Ampersand => return Ok(Object::Int(n1 & n2)),
+                    TokenType::Pipe => return Ok(Object::Int(n1 | n2)),
+                    _ => return Ok(Object::Int(n1 ^ n2)),
+                }
+            }
+            // Shifts are arithmetic, `>>` keeps the sign and `<<` errors on overflow
+            TokenType::LessLess | TokenType::GreaterGreater => {
+                let n1 = Self::get_bitwise_operand(&left_value, operator)?;
+                let n2 = Self::get_bitwise_operand(&right_value, operator)?;
+                if n2 < 0 {
+                    return Err(NoaTermination::Error(NoaError {
+                        line: operator.line,
+                        location: operator.lexeme.clone(),
+                        message: format!("Cannot shift by a negative amount {}", n2),
+                    }));
+                }
+                let shift = n2.min(63) as u32;
+                if let TokenType::GreaterGreater = operator.token_type {
+                    return Ok(Object::Int(n1 >> shift));
+                }
+                let shifted = n1 << shift;
+                if n1 != 0 && (n2 > 63 || shifted >> shift != n1) {
+                    return Self::checked(None, operator);
+                }
+                return Ok(Object::Int(shifted));
+            }
 
-                        let n2 =
-                            Self::get_number_object(right_value, binary_expression.operator.line)?;
+            _ => {
+                return Err(NoaTermination::Error(NoaError {
+                    line: operator.line,
+                    location: operator.lexeme.clone(),
+                    message: format!("Unknown binary operator"),
+                }));
+            }
+        }
+    }
 
-                        return Ok(Object::Number(n1 * n2));
-                    }
-                    TokenType::Slash => {
-                        let n1 =
-                            Self::get_number_object(left_value, binary_expression.operator.line)?;
+    /// Evaluates a link of a call and key access chain such as `a?.b["c"]()`. `None`
+    /// means an optional link found nil, which skips the rest of the chain.
+    fn evaluate_chain(
+        self: &Self,
+        environment: Arc<Mutex<Environment>>,
+    ) -> Result<Option<Object>, NoaTermination> {
+        match self {
+            Expression::Call(call_expression) => {
+                let callee = match call_expression.callee.evaluate_chain(environment.clone())? {
+                    Some(callee) => callee,
+                    None => return Ok(None),
+                };
+                if call_expression.optional
+                    && let Object::Nil = callee
+                {
+                    return Ok(None);
+                }
+                return Ok(Some(Self::call(call_expression, callee, environment)?));
+            }
+            Expression::KeyAccess(key_access) => {
+                let target = match key_access.target.evaluate_chain(environment.clone())? {
+                    Some(target) => target,
+                    None => return Ok(None),
+                };
+                if key_access.optional
+                    && let Object::Nil = target
+                {
+                    return Ok(None);
+                }
+                let key = key_access.key.evaluate(environment.clone())?;
+                return Ok(Some(Self::get_key(
+                    &target,
+                    &key,
+                    &key_access.left_bracket,
+                )?));
+            }
+            _ => return Ok(Some(self.evaluate(environment)?)),
+        }
+    }
 
-                        let n2 =
-                            Self::get_number_object(right_value, binary_expression.operator.line)?;
-                        if n2 == 0.0 {
-                            return Err(NoaTermination::Error(NoaError {
-                                line: binary_expression.operator.line,
-                                location: n2.to_string(),
-                                message: format!("Cannot divide by zero"),
-                            }));
+    /// Calls `callee` with the evaluated arguments of `call_expression`.
+    fn call(
+        call_expression: &CallExpression,
+        callee: Object,
+        environment: Arc<Mutex<Environment>>,
+    ) -> Result<Object, NoaTermination> {
+        let mut arguments: Vec<Object> = Vec::new();
+        match callee {
+            Object::Function(function) => {
+                if function.variadic && function.params.len() > call_expression.arguments.len() {
+                    return Err(NoaTermination::Error(NoaError {
+                        line: call_expression.paren.line,
+                        location: "(".to_owned(),
+                        message: format!(
+                            "Expected at least {} arguments got {} arguments",
+                            function.params.len(),
+                            call_expression.arguments.len()
+                        ),
+                    }));
+                }
+                if !function.variadic && function.params.len() != call_expression.arguments.len() {
+                    return Err(NoaTermination::Error(NoaError {
+                        line: call_expression.paren.line,
+                        location: "(".to_owned(),
+                        message: format!(
+                            "Expected {} arguments got {} arguments",
+                            function.params.len(),
+                            call_expression.arguments.len()
+                        ),
+                    }));
+                }
+                for arg in call_expression.arguments.iter() {
+                    let value = arg.evaluate(environment.clone())?;
+                    arguments.push(value.clone());
+                }
+                // Natives don't know where they were called from
+                return function
+                    .call(arguments)
+                    .map_err(|termination| match termination {
+                        NoaTermination::Error(error) if error.line == 0 => {
+                            NoaTermination::Error(NoaError {
+                                line: call_expression.paren.line,
+                                ..error
+                            })
                         }
-                        return Ok(Object::Number(n1 / n2));
-                    }
+                        _ => termination,
+                    });
+            }
+            _ => {
+                return Err(NoaTermination::Error(NoaError {
+                    line: call_expression.paren.line,
+                    location: "(".to_owned(),
+                    message: format!("{} is not callable", callee.to_string()),
+                }));
+            }
+        }
+    }
 
-                    _ => {
-                        return Err(NoaTermination::Error(NoaError {
-                            line: binary_expression.operator.line,
-                            location: binary_expression.operator.lexeme.clone(),
-                            message: format!("Unknown binary operator"),
-                        }));
-                    }
-                }
+    pub fn evaluate(
+        self: &Self,
+        environment: Arc<Mutex<Environment>>,
+    ) -> Result<Object, NoaTermination> {
+        match self {
+            Expression::Binary(binary_expression) => {
+                let left_value = binary_expression.left.evaluate(environment.clone())?;
+                let right_value = binary_expression.right.evaluate(environment.clone())?;
+
+                return Self::evaluate_binary(left_value, &binary_expression.operator, right_value);
             }
             Expression::Group(group_expression) => {
                 return group_expression.expression.evaluate(environment);
@@ -244,11 +598,19 @@ impl Expression {
                         return Ok(Object::Bool(!right_value.is_truthy()));
                     }
                     TokenType::Minus => {
+                        if let Object::Int(n1) = right_value {
+                            return Self::checked(n1.checked_neg(), &unary_expression.operator);
+                        }
                         let n1 =
                             Self::get_number_object(right_value, unary_expression.operator.line)?;
 
                         return Ok(Object::Number(-n1));
                     }
+                    TokenType::Tilde => {
+                        let n1 =
+                            Self::get_bitwise_operand(&right_value, &unary_expression.operator)?;
+                        return Ok(Object::Int(!n1));
+                    }
                     _ => {
                         return Err(NoaTermination::Error(NoaError {
                             line: unary_expression.operator.line,
@@ -262,29 +624,31 @@ impl Expression {
                 let check = ternary_expression.check.evaluate(environment.clone())?;
 
                 if check.is_truthy() {
-                    return Ok(ternary_expression.if_true.evaluate(environment.clone())?);
+                    return ternary_expression.if_true.evaluate(environment.clone());
                 } else {
-                    return Ok(ternary_expression.if_false.evaluate(environment.clone())?);
+                    return ternary_expression.if_false.evaluate(environment.clone());
                 }
             }
             Expression::Assign(assgin_expression) => {
-                let value = assgin_expression
-                    .expression
-                    .evaluate(environment.clone())?
-                    .clone();
-                match environment.lock() {
-                    Ok(mut mutex) => {
-                        mutex.assign(&assgin_expression.token, value.clone())?;
-                    }
-                    Err(_) => {
-                        return Err(NoaTermination::Error(NoaError {
-                            line: assgin_expression.token.line,
-                            location: assgin_expression.token.lexeme.clone(),
-                            message: format!("Failed to get local scope memory"),
-                        }));
+                let token = &assgin_expression.token;
+                let current = match &assgin_expression.operator {
+                    Some(_) => Some(Self::lock(&environment, token)?.get(token)?),
+                    None => None,
+                };
+                let value = assgin_expression.expression.evaluate(environment.clone())?;
+                let (value, result) = match (&assgin_expression.operator, current) {
+                    (Some(operator), Some(current)) => {
+                        let value = Self::evaluate_binary(current.clone(), operator, value)?;
+                        if assgin_expression.postfix {
+                            (value, current)
+                        } else {
+                            (value.clone(), value)
+                        }
                     }
-                }
-                return Ok(value);
+                    _ => (value.clone(), value),
+                };
+                Self::lock(&environment, token)?.assign(token, value)?;
+                return Ok(result);
             }
             Expression::Variable(variable_expression) => match environment.lock() {
                 Ok(mut mutex) => {
@@ -327,133 +691,229 @@ impl Expression {
                             }
                         }
                     }
+                    // Only nil falls through, unlike `or` which also skips false
+                    TokenType::QuestionQuestion => {
+                        if let Object::Nil = left {
+                            return logical_expression.right.evaluate(environment.clone());
+                        }
+                        return Ok(left);
+                    }
                     _ => {}
                 }
                 return Ok(Object::Bool(false));
             }
-            Expression::Call(call_expression) => {
-                let callee = call_expression.callee.evaluate(environment.clone())?;
-                let mut arguments: Vec<Object> = Vec::new();
+            Expression::Call(_) => {
+                return Ok(self.evaluate_chain(environment)?.unwrap_or(Object::Nil));
+            }
+            Expression::Table(table_expression) => {
+                let mut table = Table::new();
 
-                match callee {
-                    Object::Function(function) => {
-                        if function.params.len() != call_expression.arguments.len() {
-                            return Err(NoaTermination::Error(NoaError {
-                                line: call_expression.paren.line,
-                                location: "(".to_owned(),
-                                message: format!(
-                                    "Expected {} arguments got {} arguments",
-                                    function.params.len(),
-                                    call_expression.arguments.len()
-                                ),
-                            }));
+                for (key, val) in table_expression.values.iter() {
+                    table.insert(key.to_owned(), val.evaluate(environment.clone())?);
+                }
+
+                return Ok(Object::Table(Arc::new(Mutex::new(table))));
+            }
+            Expression::List(list_expression) => {
+                let mut values: Vec<Object> = Vec::new();
+
+                for val in list_expression.values.iter() {
+                    values.push(val.evaluate(environment.clone())?);
+                }
+
+                return Ok(Object::List(Arc::new(Mutex::new(values))));
+            }
+            Expression::Template(template_expression) => {
+                let mut output = String::new();
+                for part in template_expression.parts.iter() {
+                    output.push_str(&part.evaluate(environment.clone())?.to_string());
+                }
+                return Ok(Object::String(output));
+            }
+            Expression::KeyAccess(_) => {
+                return Ok(self.evaluate_chain(environment)?.unwrap_or(Object::Nil));
+            }
+            Expression::Match(match_expression) => {
+                let value = match_expression.value.evaluate(environment.clone())?;
+                for arm in match_expression.arms.iter() {
+                    for pattern in arm.patterns.iter() {
+                        let mut bindings = Vec::new();
+                        if !pattern.matches(&value, &mut bindings)? {
+                            continue;
+                        }
+                        let mut scope = Environment::new();
+                        scope.enclose(environment.clone());
+                        for (name, value) in bindings {
+                            scope.define(name, value);
+                        }
+                        let scope = Arc::new(Mutex::new(scope));
+                        match &arm.body {
+                            MatchBody::Expression(expression) => return expression.evaluate(scope),
+                            MatchBody::Block(block) => {
+                                block.execute(scope)?;
+                                return Ok(Object::Nil);
+                            }
                         }
-                        for (i, arg) in call_expression.arguments.iter().enumerate() {
-                            let value = arg.evaluate(environment.clone())?;
-                            arguments.push(value.clone());
+                    }
+                }
+                return Ok(Object::Nil);
+            }
+            Expression::KeyAccessAssign(key_access_assignment) => {
+                let key_access = match key_access_assignment.key_access.as_ref() {
+                    Expression::KeyAccess(key_access) => key_access,
+                    _ => return Ok(Object::Nil),
+                };
+                let target = key_access.target.evaluate(environment.clone())?;
+                let key = key_access.key.evaluate(environment.clone())?;
+                let value = key_access_assignment
+                    .expression
+                    .evaluate(environment.clone())?;
+                let (value, result) = match &key_access_assignment.operator {
+                    Some(operator) => {
+                        let current = Self::get_key(&target, &key, &key_access.left_bracket)?;
+                        let value = Self::evaluate_binary(current.clone(), operator, value)?;
+                        if key_access_assignment.postfix {
+                            (value, current)
+                        } else {
+                            (value.clone(), value)
                         }
-                        return function.call(arguments);
                     }
+                    None => (value.clone(), value),
+                };
+                Self::set_key(&target, key, value, &key_access.left_bracket)?;
+                return Ok(result);
+            }
+        }
+    }
+
+    /// Reads `target[key]`. Missing keys and out of range indexes are nil.
+    fn get_key(
+        target: &Object,
+        key: &Object,
+        left_bracket: &Token,
+    ) -> Result<Object, NoaTermination> {
+        match target {
+            Object::Table(mutex) => match mutex.lock() {
+                Ok(table) => match key {
+                    Object::String(key) => Ok(table.get_value(key.clone())),
                     _ => {
                         return Err(NoaTermination::Error(NoaError {
-                            line: call_expression.paren.line,
-                            location: "(".to_owned(),
-                            message: format!("{} is not callable", callee.to_string()),
+                            line: left_bracket.line,
+                            location: left_bracket.lexeme.clone(),
+                            message: format!("Key must be a string"),
                         }));
                     }
+                },
+                Err(_) => {
+                    return Err(NoaTermination::Error(NoaError {
+                        line: left_bracket.line,
+                        location: left_bracket.lexeme.clone(),
+                        message: format!("Failed to lock table"),
+                    }));
                 }
-            }
-            Expression::Table(table_expression) => {
-                let mut values: HashMap<String, Object> = HashMap::new();
-
-                for (key, val) in table_expression.values.iter() {
-                    values.insert(key.to_owned(), val.evaluate(environment.clone())?);
+            },
+            Object::List(mutex) => match mutex.lock() {
+                Ok(list) => {
+                    let index = Self::get_list_index(key, left_bracket)?;
+                    Ok(index
+                        .and_then(|index| list.get(index).cloned())
+                        .unwrap_or(Object::Nil))
                 }
-
-                return Ok(Object::Table(Arc::new(Mutex::new(Table { values }))));
+                Err(_) => {
+                    return Err(NoaTermination::Error(NoaError {
+                        line: left_bracket.line,
+                        location: left_bracket.lexeme.clone(),
+                        message: format!("Failed to lock list"),
+                    }));
+                }
+            },
+            _ => {
+                return Err(NoaTermination::Error(NoaError {
+                    line: left_bracket.line,
+                    location: left_bracket.lexeme.clone(),
+                    message: format!("Key access expression can only be used on tables and lists"),
+                }));
             }
-            Expression::KeyAccess(key_access) => {
-                let target = key_access.target.evaluate(environment.clone())?;
-                let key = key_access.key.evaluate(environment.clone())?;
-                match target {
-                    Object::Table(mutex) => match mutex.lock() {
-                        Ok(table) => match key {
-                            Object::String(key) => Ok(table.get_value(key)),
-                            _ => {
-                                return Err(NoaTermination::Error(NoaError {
-                                    line: key_access.left_bracket.line,
-                                    location: key_access.left_bracket.lexeme.clone(),
-                                    message: format!("Key must be a string"),
-                                }));
-                            }
-                        },
-                        Err(_) => {
+        }
+    }
+
+    /// Stores `target[key] = value`. Assigning one past the end of a list appends.
+    fn set_key(
+        target: &Object,
+        key: Object,
+        value: Object,
+        left_bracket: &Token,
+    ) -> Result<(), NoaTermination> {
+        match target {
+            Object::Table(mutex) => match key {
+                Object::String(key) => match mutex.lock() {
+                    Ok(mut table) => {
+                        if let Err(message) = table.set_value(key, value) {
                             return Err(NoaTermination::Error(NoaError {
-                                line: key_access.left_bracket.line,
-                                location: key_access.left_bracket.lexeme.clone(),
-                                message: format!("Failed to lock table"),
+                                line: left_bracket.line,
+                                location: left_bracket.lexeme.clone(),
+                                message,
                             }));
                         }
-                    },
-                    _ => {
+                    }
+                    Err(_) => {
                         return Err(NoaTermination::Error(NoaError {
-                            line: key_access.left_bracket.line,
-                            location: key_access.left_bracket.lexeme.clone(),
-                            message: format!("Key access expression can only be used on tables"),
+                            line: left_bracket.line,
+                            location: left_bracket.lexeme.clone(),
+                            message: format!("Failed to lock table"),
                         }));
                     }
+                },
+                _ => {
+                    return Err(NoaTermination::Error(NoaError {
+                        line: left_bracket.line,
+                        location: left_bracket.lexeme.clone(),
+                        message: format!("Key must be a string"),
+                    }));
                 }
-            }
-            Expression::KeyAccessAssign(key_access_assignment) => {
-                match *key_access_assignment.key_access.clone() {
-                    Expression::KeyAccess(key_access) => {
-                        let key = key_access.key.evaluate(environment.clone())?;
-                        let value = key_access_assignment
-                            .expression
-                            .evaluate(environment.clone())?;
-                        match key {
-                            Object::String(key) => {
-                                let target = key_access.target.evaluate(environment.clone())?;
-                                match target {
-                                    Object::Table(mutex) => match mutex.lock() {
-                                        Ok(mut table) => {
-                                            table.set_value(key, value);
-                                        }
-                                        Err(_) => {
-                                            return Err(NoaTermination::Error(NoaError {
-                                                line: key_access.left_bracket.line,
-                                                location: key_access.left_bracket.lexeme.clone(),
-                                                message: format!("Failed to lock table"),
-                                            }));
-                                        }
-                                    },
-                                    _ => {
-                                        return Err(NoaTermination::Error(NoaError {
-                                            line: key_access.left_bracket.line,
-                                            location: key_access.left_bracket.lexeme.clone(),
-                                            message: format!(
-                                                "Key access expression can only be used on tables"
-                                            ),
-                                        }));
-                                    }
-                                }
-                            }
-                            _ => {
-                                return Err(NoaTermination::Error(NoaError {
-                                    line: key_access.left_bracket.line,
-                                    location: key_access.left_bracket.lexeme.clone(),
-                                    message: format!("Key must be a string"),
-                                }));
-                            }
+            },
+            Object::List(mutex) => {
+                let index = Self::get_list_index(&key, left_bracket)?;
+                match mutex.lock() {
+                    Ok(mut list) => match index {
+                        Some(index) if index < list.len() => {
+                            list[index] = value;
+                        }
+                        Some(index) if index == list.len() => {
+                            list.push(value);
+                        }
+                        _ => {
+                            return Err(NoaTermination::Error(NoaError {
+                                line: left_bracket.line,
+                                location: left_bracket.lexeme.clone(),
+                                message: format!(
+                                    "List index {} out of range for length {}",
+                                    key,
+                                    list.len()
+                                ),
+                            }));
                         }
+                    },
+                    Err(_) => {
+                        return Err(NoaTermination::Error(NoaError {
+                            line: left_bracket.line,
+                            location: left_bracket.lexeme.clone(),
+                            message: format!("Failed to lock list"),
+                        }));
                     }
-                    _ => {}
                 }
-
-                return Ok(Object::Nil);
+            }
+            _ => {
+                return Err(NoaTermination::Error(NoaError {
+                    line: left_bracket.line,
+                    location: left_bracket.lexeme.clone(),
+                    message: format!("Key access expression can only be used on tables and lists"),
+                }));
             }
         }
+        return Ok(());
     }
+
     pub fn print(self: &Self) -> String {
         match self {
             Expression::Ternary(ternary) => {
@@ -473,16 +933,24 @@ impl Expression {
                 );
             }
             Expression::Group(group) => {
-                return self.parenthesize(&String::from("group"), &[group.expression.clone()]);
+                return self.parenthesize(
+                    &String::from("group"),
+                    std::slice::from_ref(&group.expression),
+                );
             }
             Expression::Literal(literal) => return literal.value.to_string(),
             Expression::Unary(unary) => {
-                return self.parenthesize(&unary.operator.lexeme, &[unary.right.clone()]);
+                return self
+                    .parenthesize(&unary.operator.lexeme, std::slice::from_ref(&unary.right));
             }
             Expression::Assign(assgin_expression) => {
+                let operator = match &assgin_expression.operator {
+                    Some(operator) => operator.lexeme.clone(),
+                    None => "=".to_owned(),
+                };
                 return self.parenthesize(
-                    &format!("{} =", assgin_expression.token.lexeme),
-                    &[assgin_expression.expression.clone()],
+                    &format!("{} {}", assgin_expression.token.lexeme, operator),
+                    std::slice::from_ref(&assgin_expression.expression),
                 );
             }
             Expression::Variable(variable_expression) => {
@@ -508,20 +976,46 @@ impl Expression {
             Expression::Table(table_expression) => {
                 let expressions: Vec<Box<Expression>> = table_expression
                     .values
-                    .values()
-                    .map(|arg| Box::new(arg.clone()))
+                    .iter()
+                    .map(|(_, arg)| Box::new(arg.clone()))
                     .collect();
                 return self.parenthesize(&format!("table"), &expressions);
             }
+            Expression::List(list_expression) => {
+                let expressions: Vec<Box<Expression>> = list_expression
+                    .values
+                    .iter()
+                    .map(|arg| Box::new(arg.clone()))
+                    .collect();
+                return self.parenthesize(&format!("list"), &expressions);
+            }
+            Expression::Template(template_expression) => {
+                let expressions: Vec<Box<Expression>> = template_expression
+                    .parts
+                    .iter()
+                    .map(|part| Box::new(part.clone()))
+                    .collect();
+                return self.parenthesize(&format!("template"), &expressions);
+            }
             Expression::KeyAccess(key_access) => {
                 return self.parenthesize(
                     &format!("[]"),
                     &[key_access.target.clone(), key_access.key.clone()],
                 );
             }
+            Expression::Match(match_expression) => {
+                return self.parenthesize(
+                    &format!("match"),
+                    std::slice::from_ref(&match_expression.value),
+                );
+            }
             Expression::KeyAccessAssign(table_assgin_expression) => {
+                let operator = match &table_assgin_expression.operator {
+                    Some(operator) => operator.lexeme.clone(),
+                    None => "=".to_owned(),
+                };
                 return self.parenthesize(
-                    &format!("[] ="),
+                    &format!("[] {}", operator),
                     &[
                         table_assgin_expression.key_access.clone(),
                         table_assgin_expression.expression.clone(),
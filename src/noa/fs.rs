@@ -0,0 +1,222 @@
+use std::{
+    fs,
+    io::Write,
+    path::{Component, Path, PathBuf},
+    sync::{Arc, Mutex},
+};
+
+use crate::noa::{
+    error::{NoaError, NoaTermination},
+    string::get_string_argument,
+    types::{NativeCallback, Object},
+};
+
+/// Directory the filesystem natives are confined to. `None` disables them.
+pub type SharedFsRoot = Arc<Mutex<Option<PathBuf>>>;
+
+fn fs_error(function: &str, message: String) -> NoaTermination {
+    return NoaTermination::Error(NoaError {
+        line: 0,
+        location: function.to_owned(),
+        message,
+    });
+}
+
+/// Maps `path` onto `root`. Absolute paths, `..` that would leave the root, symlinks
+/// pointing outside of it and symlinks to missing targets are rejected.
+pub fn sandboxed_path(root: &Path, path: &Path) -> Result<PathBuf, String> {
+    let outside = || format!("Path \"{}\" is outside of the root", path.display());
+
+    let mut relative = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::Normal(part) => relative.push(part),
+            Component::CurDir => {}
+            Component::ParentDir => {
+                if !relative.pop() {
+                    return Err(outside());
+                }
+            }
+            Component::RootDir | Component::Prefix(_) => return Err(outside()),
+        }
+    }
+    let full = root.join(&relative);
+
+    // Symlinks can still point anywhere, so every link along the path has to
+    // resolve inside the root. A link whose target doesn't exist can't be
+    // checked and would let writes create files anywhere, so it is rejected
+    let canonical_root = match root.canonicalize() {
+        Ok(root) => root,
+        Err(_) => return Err(format!("Root {} does not exist", root.display())),
+    };
+    let mut current = canonical_root.clone();
+    for part in relative.iter() {
+        let next = current.join(part);
+        match fs::symlink_metadata(&next) {
+            Ok(metadata) if metadata.file_type().is_symlink() => match next.canonicalize() {
+                Ok(target) if target.starts_with(&canonical_root) => current = target,
+                _ => return Err(outside()),
+            },
+            Ok(_) => current = next,
+            // Nothing below a missing entry exists, so there are no more links
+            Err(_) => break,
+        }
+    }
+    return Ok(full);
+}
+
+/// Maps a script path onto the fs root with `sandboxed_path`. Unless `allow_root` is set,
+/// paths that resolve to the root itself, like `""` or `"."`, are rejected.
+fn resolve(
+    root: &SharedFsRoot,
+    path: &str,
+    function: &str,
+    allow_root: bool,
+) -> Result<PathBuf, NoaTermination> {
+    let root = match root.lock() {
+        Ok(root) => match root.as_ref() {
+            Some(root) => root.clone(),
+            None => {
+                return Err(fs_error(
+                    function,
+                    "Filesystem access is disabled".to_owned(),
+                ));
+            }
+        },
+        Err(_) => return Err(fs_error(function, "Failed to lock fs root".to_owned())),
+    };
+    let full =
+        sandboxed_path(&root, Path::new(path)).map_err(|message| fs_error(function, message))?;
+    if !allow_root && full == root {
+        return Err(fs_error(
+            function,
+            format!("{} cannot be used on the root itself", function),
+        ));
+    }
+    return Ok(full);
+}
+
+/// Wraps a filesystem operation on the path given as the first argument.
+fn native(
+    name: &'static str,
+    root: SharedFsRoot,
+    operation: fn(&Path, &Vec<Object>) -> Result<Object, String>,
+) -> NativeCallback {
+    return native_on(name, root, true, operation);
+}
+
+/// Like `native`, for operations that destroy their target. These refuse the root.
+fn destructive_native(
+    name: &'static str,
+    root: SharedFsRoot,
+    operation: fn(&Path, &Vec<Object>) -> Result<Object, String>,
+) -> NativeCallback {
+    return native_on(name, root, false, operation);
+}
+
+fn native_on(
+    name: &'static str,
+    root: SharedFsRoot,
+    allow_root: bool,
+    operation: fn(&Path, &Vec<Object>) -> Result<Object, String>,
+) -> NativeCallback {
+    return Arc::new(move |arguments, _| {
+        let path = get_string_argument(arguments, 0, name)?;
+        let full = resolve(&root, &path, name, allow_root)?;
+        match operation(&full, arguments) {
+            Ok(object) => Ok(object),
+            Err(message) => Err(fs_error(
+                name,
+                format!("{} \"{}\": {}", name, path, message),
+            )),
+        }
+    });
+}
+
+fn contents_argument(arguments: &Vec<Object>) -> Result<String, String> {
+    match arguments.get(1) {
+        Some(Object::String(str)) => Ok(str.clone()),
+        other => Err(format!(
+            "expects a string as argument 2, got \"{}\"",
+            other.unwrap_or(&Object::Nil)
+        )),
+    }
+}
+
+pub fn read_file(root: SharedFsRoot) -> NativeCallback {
+    return native("read_file", root, |path, _| {
+        match fs::read_to_string(path) {
+            Ok(contents) => Ok(Object::String(contents)),
+            Err(error) => Err(error.to_string()),
+        }
+    });
+}
+
+pub fn write_file(root: SharedFsRoot) -> NativeCallback {
+    return native("write_file", root, |path, arguments| {
+        let contents = contents_argument(arguments)?;
+        match fs::write(path, contents) {
+            Ok(_) => Ok(Object::Nil),
+            Err(error) => Err(error.to_string()),
+        }
+    });
+}
+
+pub fn append_file(root: SharedFsRoot) -> NativeCallback {
+    return native("append_file", root, |path, arguments| {
+        let contents = contents_argument(arguments)?;
+        let file = fs::OpenOptions::new().create(true).append(true).open(path);
+        match file.and_then(|mut file| file.write_all(contents.as_bytes())) {
+            Ok(_) => Ok(Object::Nil),
+            Err(error) => Err(error.to_string()),
+        }
+    });
+}
+
+pub fn exists(root: SharedFsRoot) -> NativeCallback {
+    return native("exists", root, |path, _| Ok(Object::Bool(path.exists())));
+}
+
+/// Lists the names in a directory, sorted so the output doesn't depend on the platform.
+pub fn list_dir(root: SharedFsRoot) -> NativeCallback {
+    return native("list_dir", root, |path, _| {
+        let entries = match fs::read_dir(path) {
+            Ok(entries) => entries,
+            Err(error) => return Err(error.to_string()),
+        };
+        let mut names = Vec::new();
+        for entry in entries {
+            match entry {
+                Ok(entry) => names.push(entry.file_name().to_string_lossy().into_owned()),
+                Err(error) => return Err(error.to_string()),
+            }
+        }
+        names.sort();
+        Ok(Object::List(Arc::new(Mutex::new(
+            names.into_iter().map(Object::String).collect(),
+        ))))
+    });
+}
+
+/// Creates a directory along with any missing parents.
+pub fn mkdir(root: SharedFsRoot) -> NativeCallback {
+    return native("mkdir", root, |path, _| match fs::create_dir_all(path) {
+        Ok(_) => Ok(Object::Nil),
+        Err(error) => Err(error.to_string()),
+    });
+}
+
+/// Deletes a file or an empty directory, never the root itself.
+pub fn remove_file(root: SharedFsRoot) -> NativeCallback {
+    return destructive_native("remove_file", root, |path, _| {
+        let result = if path.is_dir() {
+            fs::remove_dir(path)
+        } else {
+            fs::remove_file(path)
+        };
+        match result {
+            Ok(_) => Ok(Object::Nil),
+            Err(error) => Err(error.to_string()),
+        }
+    });
+}
@@ -1,19 +1,17 @@
-use std::{
-    fmt::format,
-    sync::{Arc, Mutex},
-};
+use std::sync::{Arc, Mutex};
 
 use crate::noa::{
     environment::Environment,
     error::{NoaError, NoaTermination},
-    types::Object,
+    number::get_integer_argument,
+    types::{NativeCallback, Number, Object},
 };
 
 pub fn len(arguments: &Vec<Object>, _: Arc<Mutex<Environment>>) -> Result<Object, NoaTermination> {
     match arguments.first().unwrap() {
-        Object::String(str) => Ok(Object::Number(str.len() as f64)),
+        Object::String(str) => Ok(Object::Int(str.chars().count() as i64)),
         Object::Table(mutex) => match mutex.lock() {
-            Ok(table) => Ok(Object::Number(table.values.len() as f64)),
+            Ok(table) => Ok(Object::Int(table.values.len() as i64)),
             Err(_) => {
                 return Err(NoaTermination::Error(NoaError {
                     line: 0,
@@ -22,27 +20,140 @@ pub fn len(arguments: &Vec<Object>, _: Arc<Mutex<Environment>>) -> Result<Object
                 }));
             }
         },
+        Object::List(mutex) => match mutex.lock() {
+            Ok(list) => Ok(Object::Int(list.len() as i64)),
+            Err(_) => {
+                return Err(NoaTermination::Error(NoaError {
+                    line: 0,
+                    location: "N/A".to_owned(),
+                    message: "Failed to lock list".to_owned(),
+                }));
+            }
+        },
         _ => {
             return Err(NoaTermination::Error(NoaError {
                 line: 0,
                 location: "N/A".to_owned(),
-                message: "len can only be called on strings and tables".to_owned(),
+                message: "len can only be called on strings, tables and lists".to_owned(),
             }));
         }
     }
 }
 
 pub fn exit(arguments: &Vec<Object>, _: Arc<Mutex<Environment>>) -> Result<Object, NoaTermination> {
-    let first = arguments.first().unwrap();
+    let code = get_integer_argument(arguments, 0, "exit")?;
+    match i32::try_from(code) {
+        Ok(code) => Err(NoaTermination::Exit(code as Number)),
+        Err(_) => {
+            return Err(NoaTermination::Error(NoaError {
+                line: 0,
+                location: "exit".to_owned(),
+                message: format!("{} is not a valid exit status", code),
+            }));
+        }
+    }
+}
 
-    match first {
-        Object::Number(num) => Err(NoaTermination::Exit(*num)),
-        _ => {
+pub fn get_list_argument(
+    arguments: &Vec<Object>,
+    index: usize,
+    function: &str,
+) -> Result<Arc<Mutex<Vec<Object>>>, NoaTermination> {
+    match arguments.get(index) {
+        Some(Object::List(list)) => Ok(list.clone()),
+        other => Err(NoaTermination::Error(NoaError {
+            line: 0,
+            location: function.to_owned(),
+            message: format!(
+                "{} expects a list as argument {}, got \"{}\"",
+                function,
+                index + 1,
+                other.unwrap_or(&Object::Nil)
+            ),
+        })),
+    }
+}
+
+pub fn push(arguments: &Vec<Object>, _: Arc<Mutex<Environment>>) -> Result<Object, NoaTermination> {
+    let list = get_list_argument(arguments, 0, "push")?;
+    match list.lock() {
+        Ok(mut list) => list.push(arguments[1].clone()),
+        Err(_) => {
+            return Err(NoaTermination::Error(NoaError {
+                line: 0,
+                location: "push".to_owned(),
+                message: "Failed to lock list".to_owned(),
+            }));
+        }
+    }
+    Ok(Object::Nil)
+}
+
+pub fn pop(arguments: &Vec<Object>, _: Arc<Mutex<Environment>>) -> Result<Object, NoaTermination> {
+    let list = get_list_argument(arguments, 0, "pop")?;
+    match list.lock() {
+        Ok(mut list) => Ok(list.pop().unwrap_or(Object::Nil)),
+        Err(_) => {
             return Err(NoaTermination::Error(NoaError {
                 line: 0,
-                location: first.to_string(),
-                message: format!("{} is not a number", first.to_string()),
+                location: "pop".to_owned(),
+                message: "Failed to lock list".to_owned(),
             }));
         }
     }
 }
+
+pub fn type_of(
+    arguments: &Vec<Object>,
+    _: Arc<Mutex<Environment>>,
+) -> Result<Object, NoaTermination> {
+    Ok(Object::String(
+        arguments.first().unwrap().type_name().to_owned(),
+    ))
+}
+
+pub fn is_callable(
+    arguments: &Vec<Object>,
+    _: Arc<Mutex<Environment>>,
+) -> Result<Object, NoaTermination> {
+    Ok(Object::Bool(matches!(
+        arguments.first().unwrap(),
+        Object::Function(_)
+    )))
+}
+
+/// Number of parameters a function declares. For natives taking optional
+/// arguments this is the minimum.
+pub fn arity(
+    arguments: &Vec<Object>,
+    _: Arc<Mutex<Environment>>,
+) -> Result<Object, NoaTermination> {
+    match arguments.first().unwrap() {
+        Object::Function(function) => Ok(Object::Int(function.params.len() as i64)),
+        other => Err(NoaTermination::Error(NoaError {
+            line: 0,
+            location: "arity".to_owned(),
+            message: format!("arity expects a function, got \"{}\"", other),
+        })),
+    }
+}
+
+/// Creates the `globals()` native, listing the names defined in `globals` in sorted order.
+pub fn globals(globals: Arc<Mutex<Environment>>) -> NativeCallback {
+    return Arc::new(move |_, _| {
+        let mut names: Vec<String> = match globals.lock() {
+            Ok(globals) => globals.values().keys().cloned().collect(),
+            Err(_) => {
+                return Err(NoaTermination::Error(NoaError {
+                    line: 0,
+                    location: "globals".to_owned(),
+                    message: "Failed to lock global scope".to_owned(),
+                }));
+            }
+        };
+        names.sort();
+        Ok(Object::List(Arc::new(Mutex::new(
+            names.into_iter().map(Object::String).collect(),
+        ))))
+    });
+}
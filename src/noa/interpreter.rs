@@ -1,8 +1,7 @@
 use std::sync::{Arc, Mutex};
 
 use crate::noa::{
-    Noa,
-    environment::{self, Environment},
+    environment::Environment,
     error::NoaError,
     statement::Statement,
     types::{Number, Object},
@@ -26,7 +25,10 @@ impl Interpreter {
             Err(_) => panic!("Failed to set global object {}", name),
         }
     }
-    pub fn execute(self: &mut Self, statements: Vec<Statement>) -> Result<(Number), NoaError> {
+    pub fn globals(self: &Self) -> Arc<Mutex<Environment>> {
+        return self.environment.clone();
+    }
+    pub fn execute(self: &mut Self, statements: Vec<Statement>) -> Result<Number, NoaError> {
         for statement in statements {
             match statement.execute(self.environment.clone()) {
                 Err(e) => match e {
@@ -1,38 +1,74 @@
 use std::{
-    io::Write,
+    io::{BufRead, Write},
     sync::{Arc, Mutex},
 };
 
-use crate::noa::{environment::Environment, error::NoaTermination, types::Object};
+use crate::noa::{
+    error::{NoaError, NoaTermination},
+    types::{NativeCallback, Object},
+};
 
-pub fn print(
-    arguments: &Vec<Object>,
-    _: Arc<Mutex<Environment>>,
-) -> Result<Object, NoaTermination> {
-    for arg in arguments {
-        print!("{}", arg.to_string());
-    }
-    std::io::stdout().flush().expect("Failed to flush stdout");
-    Ok(Object::Nil)
+pub type SharedWriter = Arc<Mutex<Box<dyn Write + Send>>>;
+pub type SharedReader = Arc<Mutex<Box<dyn BufRead + Send>>>;
+
+fn io_error(message: &str) -> NoaTermination {
+    return NoaTermination::Error(NoaError {
+        line: 0,
+        location: "N/A".to_owned(),
+        message: message.to_owned(),
+    });
 }
 
-pub fn println(
+fn write_arguments(
+    stream: &SharedWriter,
     arguments: &Vec<Object>,
-    _: Arc<Mutex<Environment>>,
+    newline: bool,
 ) -> Result<Object, NoaTermination> {
+    let mut stream = match stream.lock() {
+        Ok(stream) => stream,
+        Err(_) => return Err(io_error("Failed to lock output stream")),
+    };
     for arg in arguments {
-        print!("{}", arg.to_string());
+        if write!(stream, "{}", arg).is_err() {
+            return Err(io_error("Failed to write to output stream"));
+        }
+    }
+    if newline && writeln!(stream).is_err() {
+        return Err(io_error("Failed to write to output stream"));
+    }
+    if stream.flush().is_err() {
+        return Err(io_error("Failed to flush output stream"));
     }
-    println!("");
     Ok(Object::Nil)
 }
 
-pub fn input(_: &Vec<Object>, _: Arc<Mutex<Environment>>) -> Result<Object, NoaTermination> {
-    let mut input = String::new();
-    match std::io::stdin().read_line(&mut input) {
-        Ok(_) => {}
-        Err(_) => return Ok(Object::Nil),
-    }
-    input = input.replace("\n", "");
-    Ok(Object::String(input))
+pub fn print(stdout: SharedWriter) -> NativeCallback {
+    return Arc::new(move |arguments, _| write_arguments(&stdout, arguments, false));
+}
+
+pub fn println(stdout: SharedWriter) -> NativeCallback {
+    return Arc::new(move |arguments, _| write_arguments(&stdout, arguments, true));
+}
+
+pub fn eprint(stderr: SharedWriter) -> NativeCallback {
+    return Arc::new(move |arguments, _| write_arguments(&stderr, arguments, false));
+}
+
+pub fn eprintln(stderr: SharedWriter) -> NativeCallback {
+    return Arc::new(move |arguments, _| write_arguments(&stderr, arguments, true));
+}
+
+pub fn input(stdin: SharedReader) -> NativeCallback {
+    return Arc::new(move |_, _| {
+        let mut input = String::new();
+        match stdin.lock() {
+            Ok(mut stdin) => match stdin.read_line(&mut input) {
+                Ok(_) => {}
+                Err(_) => return Ok(Object::Nil),
+            },
+            Err(_) => return Err(io_error("Failed to lock input stream")),
+        }
+        input = input.replace("\n", "");
+        Ok(Object::String(input))
+    });
 }
@@ -0,0 +1,446 @@
+use std::{
+    fmt::Write,
+    sync::{Arc, Mutex},
+};
+
+use crate::noa::{
+    environment::Environment,
+    error::{NoaError, NoaTermination},
+    string::get_string_argument,
+    types::{Number, Object, Table},
+};
+
+fn json_error(function: &str, message: String) -> NoaTermination {
+    return NoaTermination::Error(NoaError {
+        line: 0,
+        location: function.to_owned(),
+        message,
+    });
+}
+
+fn encode_string(str: &str, output: &mut String) {
+    output.push('"');
+    for c in str.chars() {
+        match c {
+            '"' => output.push_str("\\\""),
+            '\\' => output.push_str("\\\\"),
+            '\n' => output.push_str("\\n"),
+            '\r' => output.push_str("\\r"),
+            '\t' => output.push_str("\\t"),
+            '\u{08}' => output.push_str("\\b"),
+            '\u{0C}' => output.push_str("\\f"),
+            c if (c as u32) < 0x20 => {
+                let _ = write!(output, "\\u{:04x}", c as u32);
+            }
+            c => output.push(c),
+        }
+    }
+    output.push('"');
+}
+
+/// How deeply arrays and objects may nest, so deep input can't overflow the stack.
+const MAX_DEPTH: usize = 512;
+
+fn encode_newline(output: &mut String, indent: Option<usize>, depth: usize) {
+    if let Some(indent) = indent {
+        output.push('\n');
+        output.push_str(&" ".repeat(indent * depth));
+    }
+}
+
+fn encode(
+    object: &Object,
+    output: &mut String,
+    indent: Option<usize>,
+    depth: usize,
+    visiting: &mut Vec<usize>,
+) -> Result<(), String> {
+    match object {
+        Object::Nil => output.push_str("null"),
+        Object::Bool(b) => output.push_str(if *b { "true" } else { "false" }),
+        Object::Number(num) => {
+            if !num.is_finite() {
+                return Err(format!("Cannot encode {} as JSON", num));
+            }
+            let _ = write!(output, "{}", num);
+        }
+        Object::Int(num) => {
+            let _ = write!(output, "{}", num);
+        }
+        Object::String(str) => encode_string(str, output),
+        Object::Function(_) => return Err("Cannot encode a function as JSON".to_owned()),
+        Object::Table(_) | Object::List(_) if depth >= MAX_DEPTH => {
+            return Err(format!(
+                "Cannot encode more than {} nested tables and lists as JSON",
+                MAX_DEPTH
+            ));
+        }
+        Object::Table(table) => {
+            let address = Arc::as_ptr(table) as *const () as usize;
+            if visiting.contains(&address) {
+                return Err("Cannot encode a table that contains itself as JSON".to_owned());
+            }
+            let entries: Vec<(String, Object)> = match table.lock() {
+                Ok(table) => table
+                    .values
+                    .iter()
+                    .map(|(key, value)| (key.clone(), value.clone()))
+                    .collect(),
+                Err(_) => return Err("Failed to lock table".to_owned()),
+            };
+            visiting.push(address);
+            output.push('{');
+            for (i, (key, value)) in entries.iter().enumerate() {
+                if i > 0 {
+                    output.push(',');
+                }
+                encode_newline(output, indent, depth + 1);
+                encode_string(key, output);
+                output.push(':');
+                if indent.is_some() {
+                    output.push(' ');
+                }
+                encode(value, output, indent, depth + 1, visiting)?;
+            }
+            if !entries.is_empty() {
+                encode_newline(output, indent, depth);
+            }
+            output.push('}');
+            visiting.pop();
+        }
+        Object::List(list) => {
+            let address = Arc::as_ptr(list) as *const () as usize;
+            if visiting.contains(&address) {
+                return Err("Cannot encode a list that contains itself as JSON".to_owned());
+            }
+            let values = match list.lock() {
+                Ok(list) => list.clone(),
+                Err(_) => return Err("Failed to lock list".to_owned()),
+            };
+            visiting.push(address);
+            output.push('[');
+            for (i, value) in values.iter().enumerate() {
+                if i > 0 {
+                    output.push(',');
+                }
+                encode_newline(output, indent, depth + 1);
+                encode(value, output, indent, depth + 1, visiting)?;
+            }
+            if !values.is_empty() {
+                encode_newline(output, indent, depth);
+            }
+            output.push(']');
+            visiting.pop();
+        }
+    }
+    Ok(())
+}
+
+struct Decoder {
+    chars: Vec<char>,
+    current: usize,
+    line: usize,
+    column: usize,
+    /// Arrays and objects currently open.
+    depth: usize,
+}
+
+impl Decoder {
+    fn new(source: &str) -> Self {
+        return Decoder {
+            chars: source.chars().collect(),
+            current: 0,
+            line: 1,
+            column: 1,
+            depth: 0,
+        };
+    }
+
+    fn error(self: &Self, message: &str) -> String {
+        return format!(
+            "Invalid JSON at line {} column {}: {}",
+            self.line, self.column, message
+        );
+    }
+
+    fn peek(self: &Self) -> Option<char> {
+        return self.chars.get(self.current).copied();
+    }
+
+    fn advance(self: &mut Self) -> Option<char> {
+        let c = self.peek()?;
+        self.current += 1;
+        if c == '\n' {
+            self.line += 1;
+            self.column = 1;
+        } else {
+            self.column += 1;
+        }
+        return Some(c);
+    }
+
+    fn skip_whitespace(self: &mut Self) {
+        while let Some(' ' | '\t' | '\n' | '\r') = self.peek() {
+            self.advance();
+        }
+    }
+
+    fn expect(self: &mut Self, expected: char) -> Result<(), String> {
+        if self.peek() != Some(expected) {
+            return Err(self.error(&format!("expected '{}'", expected)));
+        }
+        self.advance();
+        Ok(())
+    }
+
+    fn expect_word(self: &mut Self, word: &str, value: Object) -> Result<Object, String> {
+        for expected in word.chars() {
+            if self.peek() != Some(expected) {
+                return Err(self.error(&format!("expected '{}'", word)));
+            }
+            self.advance();
+        }
+        Ok(value)
+    }
+
+    fn decode_document(self: &mut Self) -> Result<Object, String> {
+        self.skip_whitespace();
+        let value = self.decode_value()?;
+        self.skip_whitespace();
+        if self.peek().is_some() {
+            return Err(self.error("unexpected trailing characters"));
+        }
+        Ok(value)
+    }
+
+    fn decode_value(self: &mut Self) -> Result<Object, String> {
+        match self.peek() {
+            Some('{') => self.decode_nested(Self::decode_object),
+            Some('[') => self.decode_nested(Self::decode_array),
+            Some('"') => Ok(Object::String(self.decode_string()?)),
+            Some('t') => self.expect_word("true", Object::Bool(true)),
+            Some('f') => self.expect_word("false", Object::Bool(false)),
+            Some('n') => self.expect_word("null", Object::Nil),
+            Some(c) if c == '-' || c.is_ascii_digit() => self.decode_number(),
+            Some(c) => Err(self.error(&format!("unexpected character '{}'", c))),
+            None => Err(self.error("unexpected end of input")),
+        }
+    }
+
+    fn decode_nested(
+        self: &mut Self,
+        decode: fn(&mut Self) -> Result<Object, String>,
+    ) -> Result<Object, String> {
+        if self.depth >= MAX_DEPTH {
+            return Err(self.error(&format!(
+                "more than {} nested arrays and objects",
+                MAX_DEPTH
+            )));
+        }
+        self.depth += 1;
+        let value = decode(self);
+        self.depth -= 1;
+        return value;
+    }
+
+    fn decode_object(self: &mut Self) -> Result<Object, String> {
+        self.expect('{')?;
+        let mut table = Table::new();
+        self.skip_whitespace();
+        if self.peek() == Some('}') {
+            self.advance();
+            return Ok(Object::Table(Arc::new(Mutex::new(table))));
+        }
+        loop {
+            self.skip_whitespace();
+            if self.peek() != Some('"') {
+                return Err(self.error("expected string as object key"));
+            }
+            let key = self.decode_string()?;
+            self.skip_whitespace();
+            self.expect(':')?;
+            self.skip_whitespace();
+            let value = self.decode_value()?;
+            // Storing nil removes a key, so members that are null are dropped
+            table.insert(key, value);
+            self.skip_whitespace();
+            match self.advance() {
+                Some(',') => continue,
+                Some('}') => break,
+                _ => return Err(self.error("expected ',' or '}' in object")),
+            }
+        }
+        Ok(Object::Table(Arc::new(Mutex::new(table))))
+    }
+
+    fn decode_array(self: &mut Self) -> Result<Object, String> {
+        self.expect('[')?;
+        let mut values: Vec<Object> = Vec::new();
+        self.skip_whitespace();
+        if self.peek() == Some(']') {
+            self.advance();
+            return Ok(Object::List(Arc::new(Mutex::new(values))));
+        }
+        loop {
+            self.skip_whitespace();
+            values.push(self.decode_value()?);
+            self.skip_whitespace();
+            match self.advance() {
+                Some(',') => continue,
+                Some(']') => break,
+                _ => return Err(self.error("expected ',' or ']' in array")),
+            }
+        }
+        Ok(Object::List(Arc::new(Mutex::new(values))))
+    }
+
+    fn decode_hex(self: &mut Self) -> Result<u32, String> {
+        let mut code = 0;
+        for _ in 0..4 {
+            match self.advance().and_then(|c| c.to_digit(16)) {
+                Some(digit) => code = code * 16 + digit,
+                None => return Err(self.error("invalid \\u escape")),
+            }
+        }
+        Ok(code)
+    }
+
+    fn decode_string(self: &mut Self) -> Result<String, String> {
+        self.expect('"')?;
+        let mut str = String::new();
+        loop {
+            match self.advance() {
+                Some('"') => return Ok(str),
+                Some('\\') => match self.advance() {
+                    Some('"') => str.push('"'),
+                    Some('\\') => str.push('\\'),
+                    Some('/') => str.push('/'),
+                    Some('b') => str.push('\u{08}'),
+                    Some('f') => str.push('\u{0C}'),
+                    Some('n') => str.push('\n'),
+                    Some('r') => str.push('\r'),
+                    Some('t') => str.push('\t'),
+                    Some('u') => {
+                        let mut code = self.decode_hex()?;
+                        // Characters outside the BMP are written as a surrogate pair
+                        if (0xD800..0xDC00).contains(&code) {
+                            if self.advance() != Some('\\') || self.advance() != Some('u') {
+                                return Err(self.error("unpaired surrogate in \\u escape"));
+                            }
+                            let low = self.decode_hex()?;
+                            if !(0xDC00..0xE000).contains(&low) {
+                                return Err(self.error("unpaired surrogate in \\u escape"));
+                            }
+                            code = 0x10000 + ((code - 0xD800) << 10) + (low - 0xDC00);
+                        }
+                        match char::from_u32(code) {
+                            Some(c) => str.push(c),
+                            None => return Err(self.error("invalid \\u escape")),
+                        }
+                    }
+                    _ => return Err(self.error("invalid escape sequence")),
+                },
+                Some(c) if (c as u32) < 0x20 => {
+                    return Err(self.error("control character in string"));
+                }
+                Some(c) => str.push(c),
+                None => return Err(self.error("unterminated string")),
+            }
+        }
+    }
+
+    fn decode_number(self: &mut Self) -> Result<Object, String> {
+        let start = self.current;
+        if self.peek() == Some('-') {
+            self.advance();
+        }
+        let is_digit = |c: Option<char>| c.is_some_and(|c| c.is_ascii_digit());
+        if !is_digit(self.peek()) {
+            return Err(self.error("expected digit"));
+        }
+        while is_digit(self.peek()) {
+            self.advance();
+        }
+        if self.peek() == Some('.') {
+            self.advance();
+            if !is_digit(self.peek()) {
+                return Err(self.error("expected digit after '.'"));
+            }
+            while is_digit(self.peek()) {
+                self.advance();
+            }
+        }
+        if let Some('e' | 'E') = self.peek() {
+            self.advance();
+            if let Some('+' | '-') = self.peek() {
+                self.advance();
+            }
+            if !is_digit(self.peek()) {
+                return Err(self.error("expected digit in exponent"));
+            }
+            while is_digit(self.peek()) {
+                self.advance();
+            }
+        }
+        let text: String = self.chars[start..self.current].iter().collect();
+        // Integers that fit stay exact, everything else is a float
+        if !text.contains(['.', 'e', 'E'])
+            && let Ok(num) = text.parse::<i64>()
+        {
+            return Ok(Object::Int(num));
+        }
+        // Numbers past the float range would parse as inf, which can't be encoded again
+        match text.parse::<Number>() {
+            Ok(num) if num.is_finite() => Ok(Object::Number(num)),
+            Ok(_) => Err(self.error(&format!("number {} is out of range", text))),
+            Err(_) => Err(self.error("invalid number")),
+        }
+    }
+}
+
+pub fn json_encode(
+    arguments: &Vec<Object>,
+    _: Arc<Mutex<Environment>>,
+) -> Result<Object, NoaTermination> {
+    if arguments.len() > 2 {
+        return Err(json_error(
+            "json_encode",
+            format!(
+                "json_encode expects at most 2 arguments got {} arguments",
+                arguments.len()
+            ),
+        ));
+    }
+    let indent = match arguments.get(1) {
+        None | Some(Object::Bool(false)) => None,
+        Some(Object::Bool(true)) => Some(2),
+        Some(other) => {
+            return Err(json_error(
+                "json_encode",
+                format!(
+                    "json_encode expects a bool as argument 2, got \"{}\"",
+                    other
+                ),
+            ));
+        }
+    };
+    let mut output = String::new();
+    match encode(&arguments[0], &mut output, indent, 0, &mut Vec::new()) {
+        Ok(_) => Ok(Object::String(output)),
+        Err(message) => Err(json_error("json_encode", message)),
+    }
+}
+
+/// `json_decode(text)` parses JSON into tables, lists, strings, numbers, bools and nil.
+/// Tables can't hold nil, so `json_decode('{"a":null}')` gives `{}`. Nulls inside
+/// arrays are kept.
+pub fn json_decode(
+    arguments: &Vec<Object>,
+    _: Arc<Mutex<Environment>>,
+) -> Result<Object, NoaTermination> {
+    let source = get_string_argument(arguments, 0, "json_decode")?;
+    match Decoder::new(&source).decode_document() {
+        Ok(object) => Ok(object),
+        Err(message) => Err(json_error("json_decode", message)),
+    }
+}
@@ -1,118 +1,456 @@
 use std::{
-    fs,
+    collections::HashMap,
+    io::{BufRead, BufReader, Write},
+    path::PathBuf,
     sync::{Arc, Mutex},
 };
 
+#[cfg(feature = "serde")]
+pub use crate::noa::value::{ValueError, from_value, to_value};
 use crate::noa::{
     environment::Environment,
     error::NoaError,
-    generic::{exit, len},
+    fs::{SharedFsRoot, append_file, exists, list_dir, mkdir, read_file, remove_file, write_file},
+    generic::{arity, exit, globals, is_callable, len, pop, push, type_of},
     interpreter::Interpreter,
-    io::{input, print, println},
+    io::{SharedReader, SharedWriter, eprint, eprintln, input, print, println},
+    json::{json_decode, json_encode},
+    module::{SharedModuleLoader, import},
+    number::{binary, binary_int, clamp, float, int, is_finite, is_nan, pow, unary, unary_int},
     parser::Parser,
+    random::{Rng, SharedRng, random, random_choice, random_int, seed, shuffle},
     scanner::Scanner,
-    string::{str, str_to_num},
-    types::{Function, Number, Object},
+    string::{
+        chars, chr, find, format_string, join, ord, pad_left, pad_right, predicate, pretty, repeat,
+        replace, split, str, str_to_num, substr, transform,
+    },
+    system::{SharedEnvOverrides, get_env, set_env},
+    table::{copy, deep_copy, entries, freeze, has, is_frozen, keys, merge, remove, values},
+    time::{SharedClock, clock, date, now, sleep},
+};
+pub use crate::noa::{
+    module::{FileModuleLoader, MemoryModuleLoader, Module, ModuleLoader},
+    time::{Clock, SystemClock},
+    types::{Function, NativeCallback, Number, Object, Table},
 };
 mod environment;
 mod error;
 mod expression;
+mod fs;
 mod generic;
 mod interpreter;
 mod io;
+mod json;
+mod module;
 mod number;
 mod parser;
+mod pattern;
+mod random;
 mod scanner;
 mod statement;
 mod string;
+mod system;
+mod table;
+mod time;
 mod token;
 mod types;
+#[cfg(feature = "serde")]
+mod value;
 pub struct Noa {
     interpreter: Interpreter,
+    module_loader: SharedModuleLoader,
+    stdin: SharedReader,
+    stdout: SharedWriter,
+    stderr: SharedWriter,
+    fs_root: SharedFsRoot,
+    args: Arc<Mutex<Vec<Object>>>,
+    env_overrides: SharedEnvOverrides,
+    clock: SharedClock,
+    rng: SharedRng,
 }
 
 impl Noa {
     pub fn new() -> Self {
         return Noa {
             interpreter: Interpreter::new(),
+            module_loader: Arc::new(Mutex::new(Box::new(FileModuleLoader::new(".")))),
+            stdin: Arc::new(Mutex::new(Box::new(BufReader::new(std::io::stdin())))),
+            stdout: Arc::new(Mutex::new(Box::new(std::io::stdout()))),
+            stderr: Arc::new(Mutex::new(Box::new(std::io::stderr()))),
+            fs_root: Arc::new(Mutex::new(None)),
+            args: Arc::new(Mutex::new(Vec::new())),
+            env_overrides: Arc::new(Mutex::new(HashMap::new())),
+            clock: Arc::new(Mutex::new(Box::new(SystemClock::new()))),
+            rng: Arc::new(Mutex::new(Rng::from_time())),
         };
     }
     pub fn load_libray(self: &mut Self) {
         let env = Arc::new(Mutex::new(Environment::new()));
         // IO
-        self.setup_global_object(
-            "println".to_owned(),
-            Object::Function(Box::new(Function {
-                params: vec!["str".to_string()],
-                body: None,
-                callback: Some(println),
-                environment: env.clone(),
-            })),
+        let stdout = self.stdout.clone();
+        let stderr = self.stderr.clone();
+        self.setup_native_function("println", &["str"], println(stdout.clone()), env.clone());
+        self.setup_native_function("print", &["str"], print(stdout), env.clone());
+        self.setup_native_function("eprintln", &["str"], eprintln(stderr.clone()), env.clone());
+        self.setup_native_function("eprint", &["str"], eprint(stderr), env.clone());
+        self.setup_native_function("input", &[], input(self.stdin.clone()), env.clone());
+
+        // String
+        self.setup_native_function("str", &["any"], Arc::new(str), env.clone());
+        self.setup_native_function("pretty", &["any", "indent"], Arc::new(pretty), env.clone());
+        self.setup_native_function("str_to_num", &["str"], Arc::new(str_to_num), env.clone());
+        self.setup_native_function(
+            "format",
+            &["fmt", "..."],
+            Arc::new(format_string),
+            env.clone(),
         );
-        self.setup_global_object(
-            "print".to_owned(),
-            Object::Function(Box::new(Function {
-                params: vec!["str".to_string()],
-                body: None,
-                callback: Some(print),
-                environment: env.clone(),
-            })),
+        self.setup_native_function("split", &["str", "sep"], Arc::new(split), env.clone());
+        self.setup_native_function("join", &["list", "sep"], Arc::new(join), env.clone());
+        self.setup_native_function(
+            "substr",
+            &["str", "start", "..."],
+            Arc::new(substr),
+            env.clone(),
         );
-        self.setup_global_object(
-            "input".to_owned(),
-            Object::Function(Box::new(Function {
-                params: vec![],
-                body: None,
-                callback: Some(input),
-                environment: env.clone(),
-            })),
+        self.setup_native_function("find", &["str", "needle"], Arc::new(find), env.clone());
+        self.setup_native_function(
+            "replace",
+            &["str", "from", "to"],
+            Arc::new(replace),
+            env.clone(),
+        );
+        self.setup_native_function(
+            "trim",
+            &["str"],
+            transform("trim", |s| s.trim().to_owned()),
+            env.clone(),
+        );
+        self.setup_native_function(
+            "trim_start",
+            &["str"],
+            transform("trim_start", |s| s.trim_start().to_owned()),
+            env.clone(),
+        );
+        self.setup_native_function(
+            "trim_end",
+            &["str"],
+            transform("trim_end", |s| s.trim_end().to_owned()),
+            env.clone(),
+        );
+        self.setup_native_function(
+            "upper",
+            &["str"],
+            transform("upper", str::to_uppercase),
+            env.clone(),
+        );
+        self.setup_native_function(
+            "lower",
+            &["str"],
+            transform("lower", str::to_lowercase),
+            env.clone(),
         );
+        self.setup_native_function(
+            "starts_with",
+            &["str", "prefix"],
+            predicate("starts_with", |s, p| s.starts_with(p)),
+            env.clone(),
+        );
+        self.setup_native_function(
+            "ends_with",
+            &["str", "suffix"],
+            predicate("ends_with", |s, p| s.ends_with(p)),
+            env.clone(),
+        );
+        self.setup_native_function(
+            "contains",
+            &["str", "needle"],
+            predicate("contains", |s, p| s.contains(p)),
+            env.clone(),
+        );
+        self.setup_native_function("repeat", &["str", "count"], Arc::new(repeat), env.clone());
+        self.setup_native_function(
+            "pad_left",
+            &["str", "width", "..."],
+            Arc::new(pad_left),
+            env.clone(),
+        );
+        self.setup_native_function(
+            "pad_right",
+            &["str", "width", "..."],
+            Arc::new(pad_right),
+            env.clone(),
+        );
+        self.setup_native_function("chars", &["str"], Arc::new(chars), env.clone());
+        self.setup_native_function("ord", &["char"], Arc::new(ord), env.clone());
+        self.setup_native_function("chr", &["code"], Arc::new(chr), env.clone());
 
-        // String
-        self.setup_global_object(
-            "str".to_owned(),
-            Object::Function(Box::new(Function {
-                params: vec!["any".to_string()],
-                body: None,
-                callback: Some(str),
-                environment: env.clone(),
-            })),
+        // Table
+        self.setup_native_function("keys", &["table"], Arc::new(keys), env.clone());
+        self.setup_native_function("values", &["table"], Arc::new(values), env.clone());
+        self.setup_native_function("entries", &["table"], Arc::new(entries), env.clone());
+        self.setup_native_function("has", &["table", "key"], Arc::new(has), env.clone());
+        self.setup_native_function("remove", &["table", "key"], Arc::new(remove), env.clone());
+        self.setup_native_function("merge", &["table", "other"], Arc::new(merge), env.clone());
+        self.setup_native_function("copy", &["value"], Arc::new(copy), env.clone());
+        self.setup_native_function("deep_copy", &["value"], Arc::new(deep_copy), env.clone());
+        self.setup_native_function("freeze", &["table"], Arc::new(freeze), env.clone());
+        self.setup_native_function("is_frozen", &["table"], Arc::new(is_frozen), env.clone());
+
+        // Filesystem
+        let fs_root = self.fs_root.clone();
+        self.setup_native_function(
+            "read_file",
+            &["path"],
+            read_file(fs_root.clone()),
+            env.clone(),
         );
-        self.setup_global_object(
-            "str_to_num".to_owned(),
-            Object::Function(Box::new(Function {
-                params: vec!["str".to_string()],
-                body: None,
-                callback: Some(str_to_num),
-                environment: env.clone(),
-            })),
+        self.setup_native_function(
+            "write_file",
+            &["path", "contents"],
+            write_file(fs_root.clone()),
+            env.clone(),
+        );
+        self.setup_native_function(
+            "append_file",
+            &["path", "contents"],
+            append_file(fs_root.clone()),
+            env.clone(),
+        );
+        self.setup_native_function("exists", &["path"], exists(fs_root.clone()), env.clone());
+        self.setup_native_function(
+            "list_dir",
+            &["path"],
+            list_dir(fs_root.clone()),
+            env.clone(),
+        );
+        self.setup_native_function("mkdir", &["path"], mkdir(fs_root.clone()), env.clone());
+        self.setup_native_function("remove_file", &["path"], remove_file(fs_root), env.clone());
+
+        // System
+        self.setup_global_object("args".to_owned(), Object::List(self.args.clone()));
+        self.setup_native_function(
+            "env",
+            &["name"],
+            get_env(self.env_overrides.clone()),
+            env.clone(),
+        );
+        self.setup_native_function(
+            "set_env",
+            &["name", "value"],
+            set_env(self.env_overrides.clone()),
+            env.clone(),
         );
 
+        // Time
+        let time = self.clock.clone();
+        self.setup_native_function("clock", &[], clock(time.clone()), env.clone());
+        self.setup_native_function("now", &[], now(time.clone()), env.clone());
+        self.setup_native_function("sleep", &["ms"], sleep(time.clone()), env.clone());
+        self.setup_native_function("date", &["format", "..."], date(time), env.clone());
+
+        // Random
+        let rng = self.rng.clone();
+        self.setup_native_function("random", &[], random(rng.clone()), env.clone());
+        self.setup_native_function(
+            "random_int",
+            &["lo", "hi"],
+            random_int(rng.clone()),
+            env.clone(),
+        );
+        self.setup_native_function(
+            "random_choice",
+            &["list"],
+            random_choice(rng.clone()),
+            env.clone(),
+        );
+        self.setup_native_function("shuffle", &["list"], shuffle(rng.clone()), env.clone());
+        self.setup_native_function("seed", &["n"], seed(rng), env.clone());
+
+        // JSON
+        self.setup_native_function(
+            "json_encode",
+            &["value", "..."],
+            Arc::new(json_encode),
+            env.clone(),
+        );
+        self.setup_native_function("json_decode", &["str"], Arc::new(json_decode), env.clone());
+
         // Number
+        // Ints stay ints where the result is always whole
+        self.setup_native_function(
+            "floor",
+            &["num"],
+            unary_int("floor", f64::floor, Some),
+            env.clone(),
+        );
+        self.setup_native_function(
+            "ceil",
+            &["num"],
+            unary_int("ceil", f64::ceil, Some),
+            env.clone(),
+        );
+        self.setup_native_function(
+            "round",
+            &["num"],
+            unary_int("round", f64::round, Some),
+            env.clone(),
+        );
+        self.setup_native_function(
+            "abs",
+            &["num"],
+            unary_int("abs", f64::abs, i64::checked_abs),
+            env.clone(),
+        );
+        self.setup_native_function("sqrt", &["num"], unary("sqrt", f64::sqrt), env.clone());
+        self.setup_native_function("exp", &["num"], unary("exp", f64::exp), env.clone());
+        self.setup_native_function("log", &["num"], unary("log", f64::ln), env.clone());
+        self.setup_native_function("log2", &["num"], unary("log2", f64::log2), env.clone());
+        self.setup_native_function("log10", &["num"], unary("log10", f64::log10), env.clone());
+        self.setup_native_function("sin", &["num"], unary("sin", f64::sin), env.clone());
+        self.setup_native_function("cos", &["num"], unary("cos", f64::cos), env.clone());
+        self.setup_native_function("tan", &["num"], unary("tan", f64::tan), env.clone());
+        self.setup_native_function("asin", &["num"], unary("asin", f64::asin), env.clone());
+        self.setup_native_function("acos", &["num"], unary("acos", f64::acos), env.clone());
+        self.setup_native_function("atan", &["num"], unary("atan", f64::atan), env.clone());
+        self.setup_native_function(
+            "atan2",
+            &["y", "x"],
+            binary("atan2", f64::atan2),
+            env.clone(),
+        );
+        self.setup_native_function("pow", &["base", "exp"], Arc::new(pow), env.clone());
+        self.setup_native_function(
+            "min",
+            &["a", "b"],
+            binary_int("min", f64::min, |a, b| Some(a.min(b))),
+            env.clone(),
+        );
+        self.setup_native_function(
+            "max",
+            &["a", "b"],
+            binary_int("max", f64::max, |a, b| Some(a.max(b))),
+            env.clone(),
+        );
+        self.setup_native_function(
+            "clamp",
+            &["num", "min", "max"],
+            Arc::new(clamp),
+            env.clone(),
+        );
+        self.setup_native_function("int", &["value"], Arc::new(int), env.clone());
+        self.setup_native_function("float", &["value"], Arc::new(float), env.clone());
+        self.setup_native_function("is_nan", &["num"], Arc::new(is_nan), env.clone());
+        self.setup_native_function("is_finite", &["num"], Arc::new(is_finite), env.clone());
+        self.setup_global_object("pi".to_owned(), Object::Number(std::f64::consts::PI));
+        self.setup_global_object("e".to_owned(), Object::Number(std::f64::consts::E));
+        self.setup_global_object("inf".to_owned(), Object::Number(f64::INFINITY));
+        self.setup_global_object("nan".to_owned(), Object::Number(f64::NAN));
 
         // Generic
-        self.setup_global_object(
-            "len".to_owned(),
-            Object::Function(Box::new(Function {
-                params: vec!["any".to_string()],
-                body: None,
-                callback: Some(len),
-                environment: env.clone(),
-            })),
+        self.setup_native_function("len", &["any"], Arc::new(len), env.clone());
+        self.setup_native_function("exit", &["num"], Arc::new(exit), env.clone());
+        self.setup_native_function("push", &["list", "value"], Arc::new(push), env.clone());
+        self.setup_native_function("pop", &["list"], Arc::new(pop), env.clone());
+        self.setup_native_function("type", &["any"], Arc::new(type_of), env.clone());
+        self.setup_native_function("is_callable", &["any"], Arc::new(is_callable), env.clone());
+        self.setup_native_function("arity", &["fn"], Arc::new(arity), env.clone());
+        let global_scope = self.interpreter.globals();
+        self.setup_native_function("globals", &[], globals(global_scope), env.clone());
+
+        // Modules
+        let globals = self.interpreter.globals();
+        self.setup_native_function(
+            "import",
+            &["name"],
+            import(self.module_loader.clone(), globals),
+            env.clone(),
         );
+    }
+    /// A trailing `"..."` in `params` makes the function variadic.
+    fn setup_native_function(
+        self: &mut Self,
+        identifier: &str,
+        params: &[&str],
+        callback: NativeCallback,
+        environment: Arc<Mutex<Environment>>,
+    ) {
+        let variadic = params.last() == Some(&"...");
+        let params = if variadic {
+            &params[..params.len() - 1]
+        } else {
+            params
+        };
         self.setup_global_object(
-            "exit".to_owned(),
+            identifier.to_owned(),
             Object::Function(Box::new(Function {
-                params: vec!["num".to_string()],
+                params: params.iter().map(|param| param.to_string()).collect(),
+                variadic,
                 body: None,
-                callback: Some(exit),
-                environment: env.clone(),
+                callback: Some(callback),
+                environment,
             })),
         );
     }
     pub fn setup_global_object(self: &mut Self, identifier: String, object: Object) {
         self.interpreter.setup_global_object(identifier, object);
     }
+    /// Replaces the loader used by `import`. Modules that were already imported stay cached.
+    pub fn set_module_loader(self: &mut Self, loader: impl ModuleLoader + 'static) {
+        match self.module_loader.lock() {
+            Ok(mut module_loader) => *module_loader = Box::new(loader),
+            Err(_) => panic!("Failed to set module loader"),
+        }
+    }
+    /// Lets the filesystem functions access files under `root`. They are disabled until this is called.
+    pub fn set_fs_root(self: &mut Self, root: impl Into<PathBuf>) {
+        match self.fs_root.lock() {
+            Ok(mut fs_root) => *fs_root = Some(root.into()),
+            Err(_) => panic!("Failed to set fs root"),
+        }
+    }
+    /// Sets the strings scripts see in the `args` list.
+    pub fn set_args(self: &mut Self, args: Vec<String>) {
+        match self.args.lock() {
+            Ok(mut list) => *list = args.into_iter().map(Object::String).collect(),
+            Err(_) => panic!("Failed to set args"),
+        }
+    }
+    /// Replaces the clock used by the time functions.
+    pub fn set_clock(self: &mut Self, clock: impl Clock + 'static) {
+        match self.clock.lock() {
+            Ok(mut shared) => *shared = Box::new(clock),
+            Err(_) => panic!("Failed to set clock"),
+        }
+    }
+    /// Seeds the generator behind `random` and friends, like calling `seed(n)` from a script.
+    pub fn set_seed(self: &mut Self, seed: u64) {
+        match self.rng.lock() {
+            Ok(mut rng) => rng.seed(seed),
+            Err(_) => panic!("Failed to set seed"),
+        }
+    }
+    /// Replaces the stream read by `input`.
+    pub fn set_stdin(self: &mut Self, stdin: impl BufRead + Send + 'static) {
+        match self.stdin.lock() {
+            Ok(mut stream) => *stream = Box::new(stdin),
+            Err(_) => panic!("Failed to set stdin"),
+        }
+    }
+    /// Replaces the stream written by `print` and `println`.
+    pub fn set_stdout(self: &mut Self, stdout: impl Write + Send + 'static) {
+        match self.stdout.lock() {
+            Ok(mut stream) => *stream = Box::new(stdout),
+            Err(_) => panic!("Failed to set stdout"),
+        }
+    }
+    /// Replaces the stream written by `eprint` and `eprintln`.
+    pub fn set_stderr(self: &mut Self, stderr: impl Write + Send + 'static) {
+        match self.stderr.lock() {
+            Ok(mut stream) => *stream = Box::new(stderr),
+            Err(_) => panic!("Failed to set stderr"),
+        }
+    }
     pub fn run(self: &mut Self, src: String) -> Result<Number, String> {
         let mut scanner = Scanner::new(src);
         if let Err(err) = scanner.scan_tokens() {
@@ -134,8 +472,8 @@ impl Noa {
             },
         }
     }
-    pub fn run_file(self: &mut Self, path: String) -> Result<(Number), String> {
-        match fs::read_to_string(path) {
+    pub fn run_file(self: &mut Self, path: String) -> Result<Number, String> {
+        match std::fs::read_to_string(path) {
             Ok(content) => {
                 let num = self.run(content)?;
                 Ok(num)
@@ -150,3 +488,9 @@ impl Noa {
         );
     }
 }
+
+impl Default for Noa {
+    fn default() -> Self {
+        return Self::new();
+    }
+}
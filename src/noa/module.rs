@@ -0,0 +1,211 @@
+use std::{
+    collections::HashMap,
+    fs,
+    path::PathBuf,
+    sync::{Arc, Mutex},
+};
+
+use crate::noa::{
+    environment::Environment,
+    error::{NoaError, NoaTermination},
+    fs::sandboxed_path,
+    parser::Parser,
+    scanner::Scanner,
+    types::{NativeCallback, Object, Table},
+};
+
+/// What a [`ModuleLoader`] resolved a module name to.
+#[derive(Debug, Clone)]
+pub enum Module {
+    /// Noa source code. It is executed once and its top-level definitions are exported.
+    Source(String),
+    /// A value provided by the host, usually a table of native functions.
+    Native(Object),
+}
+
+/// Resolves the name given to `import` into a module.
+pub trait ModuleLoader: Send {
+    fn load(self: &Self, name: &str) -> Result<Module, String>;
+}
+
+/// Default loader that reads `<root>/<name>.noa` from the filesystem. Names can't leave
+/// the root through `..`, absolute paths or symlinks.
+pub struct FileModuleLoader {
+    root: PathBuf,
+}
+
+impl FileModuleLoader {
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        return FileModuleLoader { root: root.into() };
+    }
+}
+
+impl ModuleLoader for FileModuleLoader {
+    fn load(self: &Self, name: &str) -> Result<Module, String> {
+        let mut path = PathBuf::from(name);
+        if path.extension().is_none() {
+            path.set_extension("noa");
+        }
+        // Module names can't reach outside of the root, like paths given to the fs natives
+        let path = sandboxed_path(&self.root, &path)?;
+        match fs::read_to_string(&path) {
+            Ok(source) => Ok(Module::Source(source)),
+            Err(_) => Err(format!("Failed to read module file {}", path.display())),
+        }
+    }
+}
+
+/// Loader that serves modules from memory, for embedded assets and test fixtures.
+pub struct MemoryModuleLoader {
+    modules: HashMap<String, Module>,
+}
+
+impl MemoryModuleLoader {
+    pub fn new() -> Self {
+        return MemoryModuleLoader {
+            modules: HashMap::new(),
+        };
+    }
+    pub fn add_source(self: &mut Self, name: &str, source: &str) {
+        self.modules
+            .insert(name.to_owned(), Module::Source(source.to_owned()));
+    }
+    pub fn add_native(self: &mut Self, name: &str, object: Object) {
+        self.modules.insert(name.to_owned(), Module::Native(object));
+    }
+}
+
+impl Default for MemoryModuleLoader {
+    fn default() -> Self {
+        return Self::new();
+    }
+}
+
+impl ModuleLoader for MemoryModuleLoader {
+    fn load(self: &Self, name: &str) -> Result<Module, String> {
+        match self.modules.get(name) {
+            Some(module) => Ok(module.clone()),
+            None => Err(format!("Unknown module {}", name)),
+        }
+    }
+}
+
+pub type SharedModuleLoader = Arc<Mutex<Box<dyn ModuleLoader>>>;
+
+enum CachedModule {
+    Loading,
+    Loaded(Object),
+}
+
+fn import_error(message: String) -> NoaTermination {
+    return NoaTermination::Error(NoaError {
+        line: 0,
+        location: "import".to_owned(),
+        message,
+    });
+}
+
+/// Runs module source in its own scope on top of the globals and collects its definitions.
+fn execute_module(
+    name: &str,
+    source: String,
+    globals: Arc<Mutex<Environment>>,
+) -> Result<Object, NoaTermination> {
+    // Reported at the import call, which fills in line 0, with the module's own line
+    // in the message
+    let module_error = |error: NoaError| {
+        NoaTermination::Error(NoaError {
+            line: 0,
+            location: error.location,
+            message: format!(
+                "In module \"{}\" at line {}: {}",
+                name, error.line, error.message
+            ),
+        })
+    };
+
+    let mut scanner = Scanner::new(source);
+    scanner.scan_tokens().map_err(module_error)?;
+    let mut parser = Parser::new(scanner.tokens);
+    let statements = parser.parse().map_err(module_error)?;
+
+    let mut scope = Environment::new();
+    scope.enclose(globals);
+    let scope = Arc::new(Mutex::new(scope));
+    for statement in statements {
+        match statement.execute(scope.clone()) {
+            Err(NoaTermination::Error(error)) => return Err(module_error(error)),
+            Err(NoaTermination::Return(_)) => {
+                return Err(import_error(format!(
+                    "return can only be used inside a function (module \"{}\")",
+                    name
+                )));
+            }
+            Err(termination) => return Err(termination),
+            Ok(_) => {}
+        }
+    }
+
+    let mut table = Table::new();
+    match scope.lock() {
+        Ok(scope) => {
+            for (key, value) in scope.values() {
+                table.insert(key.clone(), value.clone());
+            }
+        }
+        Err(_) => return Err(import_error("Failed to lock module scope".to_owned())),
+    };
+    return Ok(Object::Table(Arc::new(Mutex::new(table))));
+}
+
+/// Creates the `import(name)` native. Modules are loaded once and cached by name.
+pub fn import(loader: SharedModuleLoader, globals: Arc<Mutex<Environment>>) -> NativeCallback {
+    let cache: Arc<Mutex<HashMap<String, CachedModule>>> = Arc::new(Mutex::new(HashMap::new()));
+
+    return Arc::new(move |arguments, _| {
+        let name = match arguments.first().unwrap() {
+            Object::String(name) => name.clone(),
+            other => {
+                return Err(import_error(format!(
+                    "Module name must be a string, got {}",
+                    other
+                )));
+            }
+        };
+
+        match cache.lock() {
+            Ok(mut cache) => match cache.get(&name) {
+                Some(CachedModule::Loaded(object)) => return Ok(object.clone()),
+                Some(CachedModule::Loading) => {
+                    return Err(import_error(format!("Circular import of module {}", name)));
+                }
+                None => {
+                    cache.insert(name.clone(), CachedModule::Loading);
+                }
+            },
+            Err(_) => return Err(import_error("Failed to lock module cache".to_owned())),
+        }
+
+        let module = match loader.lock() {
+            Ok(loader) => loader.load(&name),
+            Err(_) => Err("Failed to lock module loader".to_owned()),
+        };
+        let result = match module {
+            Ok(Module::Native(object)) => Ok(object),
+            Ok(Module::Source(source)) => execute_module(&name, source, globals.clone()),
+            Err(message) => Err(import_error(message)),
+        };
+
+        if let Ok(mut cache) = cache.lock() {
+            match &result {
+                Ok(object) => {
+                    cache.insert(name, CachedModule::Loaded(object.clone()));
+                }
+                Err(_) => {
+                    cache.remove(&name);
+                }
+            }
+        }
+        return result;
+    });
+}
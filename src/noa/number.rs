@@ -1,10 +1,255 @@
-use std::{
-    io::Write,
-    sync::{Arc, Mutex},
-};
+use std::sync::{Arc, Mutex};
 
 use crate::noa::{
     environment::Environment,
     error::{NoaError, NoaTermination},
-    types::{Number, Object},
+    types::{NativeCallback, Number, Object},
 };
+
+pub fn get_number_argument(
+    arguments: &Vec<Object>,
+    index: usize,
+    function: &str,
+) -> Result<Number, NoaTermination> {
+    match arguments.get(index) {
+        Some(Object::Number(num)) => Ok(*num),
+        Some(Object::Int(num)) => Ok(*num as Number),
+        Some(other) => Err(NoaTermination::Error(NoaError {
+            line: 0,
+            location: function.to_owned(),
+            message: format!(
+                "{} expects a number as argument {}, got \"{}\"",
+                function,
+                index + 1,
+                other
+            ),
+        })),
+        None => Err(NoaTermination::Error(NoaError {
+            line: 0,
+            location: function.to_owned(),
+            message: format!("{} expects at least {} arguments", function, index + 1),
+        })),
+    }
+}
+
+/// Wraps a `Number -> Number` function as a native taking one number.
+pub fn unary(name: &'static str, operation: fn(Number) -> Number) -> NativeCallback {
+    return Arc::new(move |arguments, _| {
+        let num = get_number_argument(arguments, 0, name)?;
+        Ok(Object::Number(operation(num)))
+    });
+}
+
+/// Wraps a `(Number, Number) -> Number` function as a native taking two numbers.
+pub fn binary(name: &'static str, operation: fn(Number, Number) -> Number) -> NativeCallback {
+    return Arc::new(move |arguments, _| {
+        let a = get_number_argument(arguments, 0, name)?;
+        let b = get_number_argument(arguments, 1, name)?;
+        Ok(Object::Number(operation(a, b)))
+    });
+}
+
+fn overflow_error(function: &str) -> NoaTermination {
+    return NoaTermination::Error(NoaError {
+        line: 0,
+        location: function.to_owned(),
+        message: format!("Integer overflow in {}", function),
+    });
+}
+
+/// Like `unary`, but an int argument stays an int. `int_operation` returns `None` on
+/// overflow.
+pub fn unary_int(
+    name: &'static str,
+    operation: fn(Number) -> Number,
+    int_operation: fn(i64) -> Option<i64>,
+) -> NativeCallback {
+    return Arc::new(move |arguments, _| {
+        if let Some(Object::Int(num)) = arguments.first() {
+            return int_operation(*num)
+                .map(Object::Int)
+                .ok_or_else(|| overflow_error(name));
+        }
+        let num = get_number_argument(arguments, 0, name)?;
+        Ok(Object::Number(operation(num)))
+    });
+}
+
+/// Like `binary`, but gives an int when both arguments are ints. `int_operation`
+/// returns `None` on overflow.
+pub fn binary_int(
+    name: &'static str,
+    operation: fn(Number, Number) -> Number,
+    int_operation: fn(i64, i64) -> Option<i64>,
+) -> NativeCallback {
+    return Arc::new(move |arguments, _| {
+        if let (Some(Object::Int(a)), Some(Object::Int(b))) = (arguments.first(), arguments.get(1))
+        {
+            return int_operation(*a, *b)
+                .map(Object::Int)
+                .ok_or_else(|| overflow_error(name));
+        }
+        let a = get_number_argument(arguments, 0, name)?;
+        let b = get_number_argument(arguments, 1, name)?;
+        Ok(Object::Number(operation(a, b)))
+    });
+}
+
+/// `pow(base, exp)` works like `base ** exp`: ints with a non-negative exponent give an
+/// int, anything else a float.
+pub fn pow(arguments: &Vec<Object>, _: Arc<Mutex<Environment>>) -> Result<Object, NoaTermination> {
+    if let (Some(Object::Int(base)), Some(Object::Int(exp))) = (arguments.first(), arguments.get(1))
+        && *exp >= 0
+    {
+        return u32::try_from(*exp)
+            .ok()
+            .and_then(|exp| base.checked_pow(exp))
+            .map(Object::Int)
+            .ok_or_else(|| overflow_error("pow"));
+    }
+    let base = get_number_argument(arguments, 0, "pow")?;
+    let exp = get_number_argument(arguments, 1, "pow")?;
+    Ok(Object::Number(base.powf(exp)))
+}
+
+pub fn clamp(
+    arguments: &Vec<Object>,
+    _: Arc<Mutex<Environment>>,
+) -> Result<Object, NoaTermination> {
+    if let [Object::Int(num), Object::Int(min), Object::Int(max)] = arguments.as_slice() {
+        if min > max {
+            return Err(NoaTermination::Error(NoaError {
+                line: 0,
+                location: "clamp".to_owned(),
+                message: format!("clamp expects min <= max, got {} and {}", min, max),
+            }));
+        }
+        return Ok(Object::Int(*num.clamp(min, max)));
+    }
+    let num = get_number_argument(arguments, 0, "clamp")?;
+    let min = get_number_argument(arguments, 1, "clamp")?;
+    let max = get_number_argument(arguments, 2, "clamp")?;
+    if min.is_nan() || max.is_nan() {
+        return Err(NoaTermination::Error(NoaError {
+            line: 0,
+            location: "clamp".to_owned(),
+            message: format!("clamp expects numbers as bounds, got {} and {}", min, max),
+        }));
+    }
+    if min > max {
+        return Err(NoaTermination::Error(NoaError {
+            line: 0,
+            location: "clamp".to_owned(),
+            message: format!("clamp expects min <= max, got {} and {}", min, max),
+        }));
+    }
+    Ok(Object::Number(num.clamp(min, max)))
+}
+
+pub fn is_nan(
+    arguments: &Vec<Object>,
+    _: Arc<Mutex<Environment>>,
+) -> Result<Object, NoaTermination> {
+    let num = get_number_argument(arguments, 0, "is_nan")?;
+    Ok(Object::Bool(num.is_nan()))
+}
+
+pub fn is_finite(
+    arguments: &Vec<Object>,
+    _: Arc<Mutex<Environment>>,
+) -> Result<Object, NoaTermination> {
+    let num = get_number_argument(arguments, 0, "is_finite")?;
+    Ok(Object::Bool(num.is_finite()))
+}
+
+/// Whether `num` truncates to an i64 without saturating. -2^63 is exact as a float, 2^63
+/// is the first float past i64::MAX.
+fn fits_i64(num: Number) -> bool {
+    return (-9223372036854775808.0..9223372036854775808.0).contains(&num);
+}
+
+pub fn get_integer_argument(
+    arguments: &Vec<Object>,
+    index: usize,
+    function: &str,
+) -> Result<i64, NoaTermination> {
+    if let Some(Object::Int(num)) = arguments.get(index) {
+        return Ok(*num);
+    }
+    let num = get_number_argument(arguments, index, function)?;
+    if !fits_i64(num) {
+        return Err(NoaTermination::Error(NoaError {
+            line: 0,
+            location: function.to_owned(),
+            message: format!(
+                "{} argument {} is out of range, got {}",
+                function,
+                index + 1,
+                num
+            ),
+        }));
+    }
+    if num.fract() != 0.0 {
+        return Err(NoaTermination::Error(NoaError {
+            line: 0,
+            location: function.to_owned(),
+            message: format!(
+                "{} expects an integer as argument {}, got {}",
+                function,
+                index + 1,
+                num
+            ),
+        }));
+    }
+    Ok(num as i64)
+}
+
+fn conversion_error(function: &str, value: &Object) -> NoaTermination {
+    return NoaTermination::Error(NoaError {
+        line: 0,
+        location: function.to_owned(),
+        message: format!("Cannot convert \"{}\" to {}", value, function),
+    });
+}
+
+/// `int(x)` truncates floats towards zero and parses strings. Strings that aren't
+/// numbers give nil, like `str_to_num`.
+pub fn int(arguments: &Vec<Object>, _: Arc<Mutex<Environment>>) -> Result<Object, NoaTermination> {
+    let value = arguments.first().unwrap();
+    let num = match value {
+        Object::Int(num) => return Ok(Object::Int(*num)),
+        Object::Bool(b) => return Ok(Object::Int(*b as i64)),
+        Object::Number(num) => *num,
+        Object::String(str) => {
+            if let Ok(num) = str.trim().parse::<i64>() {
+                return Ok(Object::Int(num));
+            }
+            match str.trim().parse::<Number>() {
+                Ok(num) => num,
+                Err(_) => return Ok(Object::Nil),
+            }
+        }
+        _ => return Err(conversion_error("int", value)),
+    };
+    if !fits_i64(num) {
+        return Err(conversion_error("int", value));
+    }
+    Ok(Object::Int(num.trunc() as i64))
+}
+
+pub fn float(
+    arguments: &Vec<Object>,
+    _: Arc<Mutex<Environment>>,
+) -> Result<Object, NoaTermination> {
+    let value = arguments.first().unwrap();
+    match value {
+        Object::Int(num) => Ok(Object::Number(*num as Number)),
+        Object::Number(num) => Ok(Object::Number(*num)),
+        Object::Bool(b) => Ok(Object::Number(*b as i64 as Number)),
+        Object::String(str) => match str.trim().parse::<Number>() {
+            Ok(num) => Ok(Object::Number(num)),
+            Err(_) => Ok(Object::Nil),
+        },
+        _ => Err(conversion_error("float", value)),
+    }
+}
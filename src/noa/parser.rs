@@ -1,14 +1,16 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 
 use crate::noa::error::NoaError;
 use crate::noa::expression::{
     AssginExpression, BinaryExpression, CallExpression, Expression, GroupExpression,
-    KeyAccessAssignExpression, KeyAccessExpression, LiteralExpression, LogicalExpression,
-    TableExpression, TernaryExpression, UnaryExpression, VariableExpression,
+    KeyAccessAssignExpression, KeyAccessExpression, ListExpression, LiteralExpression,
+    LogicalExpression, MatchArm, MatchBody, MatchExpression, TableExpression, TemplateExpression,
+    TernaryExpression, UnaryExpression, VariableExpression,
 };
+use crate::noa::pattern::Pattern;
 use crate::noa::statement::{
-    BlockStatement, ExpressionStatement, FunctionStatement, IfStatement, ReturnStatement,
-    Statement, VarStatement, WhileStatement,
+    BlockStatement, ExpressionStatement, ForInStatement, FunctionStatement, IfStatement,
+    ReturnStatement, Statement, VarStatement, WhileStatement,
 };
 use crate::noa::token::{Token, TokenType};
 use crate::noa::types::Object;
@@ -16,11 +18,19 @@ use crate::noa::types::Object;
 pub struct Parser {
     tokens: Vec<Token>,
     current: usize,
+    /// Names declared so far in each enclosing scope, and whether they are constants.
+    /// Lets assignments to constants fail before the script runs. Names that aren't
+    /// found, like globals declared later, are still checked when assigned.
+    scopes: Vec<HashMap<String, bool>>,
 }
 
 impl Parser {
     pub fn new(tokens: Vec<Token>) -> Self {
-        return Parser { tokens, current: 0 };
+        return Parser {
+            tokens,
+            current: 0,
+            scopes: vec![HashMap::new()],
+        };
     }
 
     pub fn parse(self: &mut Self) -> Result<Vec<Statement>, NoaError> {
@@ -56,7 +66,10 @@ impl Parser {
 
     pub fn parse_declaration(self: &mut Self) -> Result<Statement, String> {
         if self.match_token_types(&[TokenType::Let]) {
-            return self.parse_var_declaration();
+            return self.parse_var_declaration(false);
+        }
+        if self.match_token_types(&[TokenType::Const]) {
+            return self.parse_var_declaration(true);
         }
         if self.match_token_types(&[TokenType::Fn]) {
             return self.parse_func_declaration("function".to_owned());
@@ -65,14 +78,14 @@ impl Parser {
         return self.parse_statement();
     }
 
-    pub fn parse_var_declaration(self: &mut Self) -> Result<Statement, String> {
-        let identifier = self
-            .consume(TokenType::Identifier, "Invalid identifier".to_string())?
-            .clone();
+    pub fn parse_var_declaration(self: &mut Self, constant: bool) -> Result<Statement, String> {
+        let identifier = self.consume_declared_name(constant, "Invalid identifier".to_string())?;
         let mut initializer: Option<Box<Expression>> = None;
         if self.match_token_types(&[TokenType::Equal]) {
             let expression = self.parse_expression()?;
             initializer = Some(Box::new(expression));
+        } else if constant {
+            return Err(format!("Expect = after constant {}", identifier.lexeme));
         }
         self.consume(
             TokenType::Semicolon,
@@ -82,13 +95,12 @@ impl Parser {
         return Ok(Statement::Var(VarStatement {
             initializer,
             identifier: identifier,
+            constant,
         }));
     }
 
     pub fn parse_func_declaration(self: &mut Self, kind: String) -> Result<Statement, String> {
-        let name = self
-            .consume(TokenType::Identifier, format!("Expect {} name", kind))?
-            .clone();
+        let name = self.consume_declared_name(false, format!("Expect {} name", kind))?;
 
         self.consume(TokenType::LeftParen, "Expect ( after name".to_owned())?;
 
@@ -115,7 +127,12 @@ impl Parser {
             "Expect ) after parameters".to_owned(),
         )?;
         self.consume(TokenType::LeftBrace, "Expect { after )".to_owned())?;
+        self.begin_scope();
+        for parameter in parameters.iter() {
+            self.declare(parameter, false)?;
+        }
         let body = self.parse_block_statement()?;
+        self.end_scope();
         return Ok(Statement::Function(FunctionStatement {
             name: name.clone(),
             params: parameters,
@@ -144,6 +161,10 @@ impl Parser {
             return self.parse_return_statement();
         }
 
+        if self.check(&TokenType::Match) {
+            return self.parse_match_statement();
+        }
+
         return self.parse_expression_statement();
     }
 
@@ -213,8 +234,19 @@ impl Parser {
     }
 
     pub fn parse_for_statement(self: &mut Self) -> Result<Statement, String> {
+        let keyword = self.previous().clone();
         self.consume(TokenType::LeftParen, "Expect ( after for.".to_string())?;
 
+        if self.check_at(0, TokenType::Identifier)
+            && (self.check_at(1, TokenType::In)
+                || (self.check_at(1, TokenType::Comma)
+                    && self.check_at(2, TokenType::Identifier)
+                    && self.check_at(3, TokenType::In)))
+        {
+            return self.parse_for_in_statement(keyword);
+        }
+        self.begin_scope();
+
         let mut initializer: Statement = Statement::Expression(ExpressionStatement {
             expression: Box::new(Expression::Literal(LiteralExpression {
                 value: Object::Nil,
@@ -231,7 +263,7 @@ impl Parser {
         // First part
         if !self.match_token_types(&[TokenType::Semicolon]) {
             if self.match_token_types(&[TokenType::Let]) {
-                initializer = self.parse_var_declaration()?;
+                initializer = self.parse_var_declaration(false)?;
             } else {
                 initializer = self.parse_expression_statement()?;
             }
@@ -256,6 +288,7 @@ impl Parser {
         }
 
         let if_true = self.parse_statement()?;
+        self.end_scope();
 
         return Ok(Statement::Block(BlockStatement {
             statements: vec![
@@ -275,12 +308,42 @@ impl Parser {
         }));
     }
 
+    pub fn parse_for_in_statement(self: &mut Self, keyword: Token) -> Result<Statement, String> {
+        let mut variables = vec![self.advance()];
+        if self.match_token_types(&[TokenType::Comma]) {
+            variables.push(self.advance());
+        }
+        self.consume(TokenType::In, "Expect in after loop variables".to_string())?;
+
+        let iterable = self.parse_expression()?;
+        self.consume(
+            TokenType::RightParen,
+            "Expect ) after expression".to_string(),
+        )?;
+
+        self.begin_scope();
+        for variable in variables.iter() {
+            self.declare(variable, false)?;
+        }
+        let body = self.parse_statement()?;
+        self.end_scope();
+
+        return Ok(Statement::ForIn(ForInStatement {
+            keyword,
+            variables,
+            iterable: Box::new(iterable),
+            body: Box::new(body),
+        }));
+    }
+
     pub fn parse_block_statement(self: &mut Self) -> Result<Statement, String> {
         let mut statements: Vec<Statement> = Vec::new();
 
+        self.begin_scope();
         while !self.check(&TokenType::RightBrace) && !self.is_at_end() {
             statements.push(self.parse_declaration()?);
         }
+        self.end_scope();
 
         self.consume(TokenType::RightBrace, "Expect } after block.".to_owned())?;
 
@@ -289,6 +352,19 @@ impl Parser {
         }));
     }
 
+    /// A `match` used as a statement ends at its closing brace, the `;` is optional. A
+    /// `(` or `[` on the next line starts a new statement instead of calling or indexing
+    /// the result.
+    pub fn parse_match_statement(self: &mut Self) -> Result<Statement, String> {
+        self.consume(TokenType::Match, "Expect match".to_string())?;
+        let expr = self.parse_match()?;
+        self.match_token_types(&[TokenType::Semicolon]);
+
+        return Ok(Statement::Expression(ExpressionStatement {
+            expression: Box::new(expr),
+        }));
+    }
+
     pub fn parse_expression_statement(self: &mut Self) -> Result<Statement, String> {
         let expr = self.parse_expression()?;
         self.consume(
@@ -301,6 +377,44 @@ impl Parser {
         }));
     }
 
+    fn begin_scope(self: &mut Self) {
+        self.scopes.push(HashMap::new());
+    }
+    fn end_scope(self: &mut Self) {
+        self.scopes.pop();
+    }
+    fn declare(self: &mut Self, name: &Token, constant: bool) -> Result<(), String> {
+        if let Some(scope) = self.scopes.last_mut() {
+            if scope.get(&name.lexeme) == Some(&true) {
+                return Err(format!("Cannot redeclare constant {}", name.lexeme));
+            }
+            scope.insert(name.lexeme.clone(), constant);
+        }
+        return Ok(());
+    }
+    /// Declares and consumes the name of a `let`, `const` or `fn`. The name is declared
+    /// while it is still the current token, so a redeclared constant is reported at it.
+    fn consume_declared_name(
+        self: &mut Self,
+        constant: bool,
+        message: String,
+    ) -> Result<Token, String> {
+        if self.check(&TokenType::Identifier) {
+            let name = self.peek().clone();
+            self.declare(&name, constant)?;
+        }
+        return Ok(self.consume(TokenType::Identifier, message)?.clone());
+    }
+    /// Whether `name` currently refers to a constant, as far as the parser knows.
+    fn is_constant(self: &Self, name: &str) -> bool {
+        for scope in self.scopes.iter().rev() {
+            if let Some(constant) = scope.get(name) {
+                return *constant;
+            }
+        }
+        return false;
+    }
+
     fn is_at_end(self: &Self) -> bool {
         return self.peek().token_type == TokenType::EOF;
     }
@@ -314,6 +428,12 @@ impl Parser {
 
         return self.peek().token_type == *token_type;
     }
+    fn check_at(self: &Self, offset: usize, token_type: TokenType) -> bool {
+        match self.tokens.get(self.current + offset) {
+            Some(token) => token.token_type == token_type,
+            None => false,
+        }
+    }
     fn previous(self: &Self) -> Token {
         return self.tokens[self.current - 1].clone();
     }
@@ -340,28 +460,108 @@ impl Parser {
     }
 
     fn parse_assignment(self: &mut Self) -> Result<Expression, String> {
-        let expr = self.parse_or()?;
+        let expr = self.parse_coalesce()?;
 
         if self.match_token_types(&[TokenType::Equal]) {
             let value = self.parse_assignment()?;
-            let expr_clone = expr.clone();
+            return self.make_assignment(expr, None, value, false);
+        }
+        if self.match_token_types(&[
+            TokenType::PlusEqual,
+            TokenType::MinusEqual,
+            TokenType::StarEqual,
+            TokenType::SlashEqual,
+            TokenType::PercentEqual,
+        ]) {
+            let operator = Self::arithmetic_operator(&self.previous());
+            let value = self.parse_assignment()?;
+            return self.make_assignment(expr, Some(operator), value, false);
+        }
+        return Ok(expr);
+    }
 
-            match expr {
-                Expression::Variable(variable) => {
-                    return Ok(Expression::Assign(AssginExpression {
-                        token: variable.token.clone(),
-                        expression: Box::new(value),
-                    }));
+    /// Builds an assignment to a variable or a key. With an operator the target's
+    /// current value is combined with `value` first, as in `x += 1`.
+    fn make_assignment(
+        self: &Self,
+        target: Expression,
+        operator: Option<Token>,
+        value: Expression,
+        postfix: bool,
+    ) -> Result<Expression, String> {
+        match target {
+            Expression::Variable(variable) => {
+                if self.is_constant(&variable.token.lexeme) {
+                    return Err(format!(
+                        "Cannot assign to constant {}",
+                        variable.token.lexeme
+                    ));
                 }
-                Expression::KeyAccess(_) => {
-                    return Ok(Expression::KeyAccessAssign(KeyAccessAssignExpression {
-                        key_access: Box::new(expr_clone),
-                        expression: Box::new(value),
-                    }));
-                }
-                _ => return Err("Invalid assignment target".to_string()),
+                return Ok(Expression::Assign(AssginExpression {
+                    token: variable.token.clone(),
+                    expression: Box::new(value),
+                    operator,
+                    postfix,
+                }));
+            }
+            Expression::KeyAccess(ref key_access) if !key_access.optional => {
+                return Ok(Expression::KeyAccessAssign(KeyAccessAssignExpression {
+                    key_access: Box::new(target),
+                    expression: Box::new(value),
+                    operator,
+                    postfix,
+                }));
             }
+            _ => return Err("Invalid assignment target".to_string()),
+        }
+    }
+
+    /// Maps `+=` and `++` to `+`, `-=` and `--` to `-`, and so on. The lexeme is kept so
+    /// errors point at the operator that was written.
+    fn arithmetic_operator(token: &Token) -> Token {
+        let token_type = match token.token_type {
+            TokenType::PlusEqual | TokenType::PlusPlus => TokenType::Plus,
+            TokenType::MinusEqual | TokenType::MinusMinus => TokenType::Minus,
+            TokenType::StarEqual => TokenType::Star,
+            TokenType::SlashEqual => TokenType::Slash,
+            TokenType::PercentEqual => TokenType::Percent,
+            other => other,
+        };
+        return Token {
+            token_type,
+            lexeme: token.lexeme.clone(),
+            line: token.line,
+            litral: Object::Nil,
+        };
+    }
+
+    /// `++x`, `--x`, `x++` and `x--` add or subtract 1.
+    fn make_increment(
+        self: &Self,
+        target: Expression,
+        token: &Token,
+        postfix: bool,
+    ) -> Result<Expression, String> {
+        let one = Expression::Literal(LiteralExpression {
+            value: Object::Int(1),
+        });
+        return self.make_assignment(target, Some(Self::arithmetic_operator(token)), one, postfix);
+    }
+
+    /// `a ?? b` is `b` only when `a` is nil. It binds looser than `or`.
+    fn parse_coalesce(self: &mut Self) -> Result<Expression, String> {
+        let mut expr = self.parse_or()?;
+
+        while self.match_token_types(&[TokenType::QuestionQuestion]) {
+            let operator = self.previous().clone();
+            let right = self.parse_or()?;
+            expr = Expression::Logical(LogicalExpression {
+                left: Box::new(expr),
+                operator: operator,
+                right: Box::new(right),
+            })
         }
+
         return Ok(expr);
     }
 
@@ -434,7 +634,7 @@ impl Parser {
     }
 
     fn parse_comparison(self: &mut Self) -> Result<Expression, String> {
-        let mut expr: Expression = self.parse_term()?;
+        let mut expr: Expression = self.parse_bitwise_or()?;
 
         while self.match_token_types(&[
             TokenType::Greater,
@@ -442,6 +642,72 @@ impl Parser {
             TokenType::Less,
             TokenType::LessEqual,
         ]) {
+            let operator = self.previous().clone();
+            let right = self.parse_bitwise_or()?;
+            expr = Expression::Binary(BinaryExpression {
+                left: Box::new(expr),
+                operator: operator,
+                right: Box::new(right),
+            })
+        }
+
+        return Ok(expr);
+    }
+
+    /// Bitwise operators bind looser than arithmetic and tighter than comparisons,
+    /// so `flags & MASK == MASK` compares the masked value.
+    fn parse_bitwise_or(self: &mut Self) -> Result<Expression, String> {
+        let mut expr: Expression = self.parse_bitwise_xor()?;
+
+        while self.match_token_types(&[TokenType::Pipe]) {
+            let operator = self.previous().clone();
+            let right = self.parse_bitwise_xor()?;
+            expr = Expression::Binary(BinaryExpression {
+                left: Box::new(expr),
+                operator: operator,
+                right: Box::new(right),
+            })
+        }
+
+        return Ok(expr);
+    }
+
+    fn parse_bitwise_xor(self: &mut Self) -> Result<Expression, String> {
+        let mut expr: Expression = self.parse_bitwise_and()?;
+
+        while self.match_token_types(&[TokenType::Caret]) {
+            let operator = self.previous().clone();
+            let right = self.parse_bitwise_and()?;
+            expr = Expression::Binary(BinaryExpression {
+                left: Box::new(expr),
+                operator: operator,
+                right: Box::new(right),
+            })
+        }
+
+        return Ok(expr);
+    }
+
+    fn parse_bitwise_and(self: &mut Self) -> Result<Expression, String> {
+        let mut expr: Expression = self.parse_shift()?;
+
+        while self.match_token_types(&[TokenType::Ampersand]) {
+            let operator = self.previous().clone();
+            let right = self.parse_shift()?;
+            expr = Expression::Binary(BinaryExpression {
+                left: Box::new(expr),
+                operator: operator,
+                right: Box::new(right),
+            })
+        }
+
+        return Ok(expr);
+    }
+
+    fn parse_shift(self: &mut Self) -> Result<Expression, String> {
+        let mut expr: Expression = self.parse_term()?;
+
+        while self.match_token_types(&[TokenType::LessLess, TokenType::GreaterGreater]) {
             let operator = self.previous().clone();
             let right = self.parse_term()?;
             expr = Expression::Binary(BinaryExpression {
@@ -473,7 +739,12 @@ impl Parser {
     fn parse_factor(self: &mut Self) -> Result<Expression, String> {
         let mut expr: Expression = self.parse_unary()?;
 
-        while self.match_token_types(&[TokenType::Star, TokenType::Slash]) {
+        while self.match_token_types(&[
+            TokenType::Star,
+            TokenType::Slash,
+            TokenType::Percent,
+            TokenType::TildeSlash,
+        ]) {
             let operator = self.previous().clone();
             let right = self.parse_unary()?;
             expr = Expression::Binary(BinaryExpression {
@@ -487,16 +758,50 @@ impl Parser {
     }
 
     fn parse_unary(self: &mut Self) -> Result<Expression, String> {
-        if self.match_token_types(&[TokenType::Bang, TokenType::Minus]) {
+        if self.match_token_types(&[TokenType::PlusPlus, TokenType::MinusMinus]) {
+            let operator = self.previous().clone();
+            let target = self.parse_unary()?;
+            return self.make_increment(target, &operator, false);
+        }
+        if self.match_token_types(&[TokenType::Bang, TokenType::Minus, TokenType::Tilde]) {
             let operator = self.previous().clone();
-            let right = self.parse_call_or_key_access()?;
+            let right = self.parse_unary()?;
             return Ok(Expression::Unary(UnaryExpression {
                 operator: operator,
                 right: Box::new(right),
             }));
         }
 
-        return self.parse_call_or_key_access();
+        return self.parse_power();
+    }
+
+    /// `**` binds tighter than unary minus on its left and groups to the right,
+    /// so `-2 ** 2` is `-4` and `2 ** 3 ** 2` is `2 ** 9`.
+    fn parse_power(self: &mut Self) -> Result<Expression, String> {
+        let expr = self.parse_postfix()?;
+
+        if self.match_token_types(&[TokenType::StarStar]) {
+            let operator = self.previous().clone();
+            let right = self.parse_unary()?;
+            return Ok(Expression::Binary(BinaryExpression {
+                left: Box::new(expr),
+                operator: operator,
+                right: Box::new(right),
+            }));
+        }
+
+        return Ok(expr);
+    }
+
+    fn parse_postfix(self: &mut Self) -> Result<Expression, String> {
+        let expr = self.parse_call_or_key_access()?;
+
+        if self.match_token_types(&[TokenType::PlusPlus, TokenType::MinusMinus]) {
+            let operator = self.previous().clone();
+            return self.make_increment(expr, &operator, true);
+        }
+
+        return Ok(expr);
     }
 
     fn parse_call_or_key_access(self: &mut Self) -> Result<Expression, String> {
@@ -504,9 +809,17 @@ impl Parser {
 
         loop {
             if self.match_token_types(&[TokenType::LeftParen]) {
-                expression = self.finish_call(expression)?;
+                expression = self.finish_call(expression, false)?;
             } else if self.match_token_types(&[TokenType::LeftSquareBracket]) {
-                expression = self.finish_access_key(expression)?;
+                expression = self.finish_access_key(expression, false)?;
+            } else if self.match_token_types(&[TokenType::QuestionBracket]) {
+                expression = self.finish_access_key(expression, true)?;
+            } else if self.match_token_types(&[TokenType::QuestionDot]) {
+                if self.match_token_types(&[TokenType::LeftParen]) {
+                    expression = self.finish_call(expression, true)?;
+                } else {
+                    expression = self.finish_access_name(expression)?;
+                }
             } else {
                 break;
             }
@@ -515,7 +828,11 @@ impl Parser {
         return Ok(expression);
     }
 
-    fn finish_call(self: &mut Self, expression: Expression) -> Result<Expression, String> {
+    fn finish_call(
+        self: &mut Self,
+        expression: Expression,
+        optional: bool,
+    ) -> Result<Expression, String> {
         let mut arguments: Vec<Expression> = Vec::new();
 
         if !self.check(&TokenType::RightParen) {
@@ -539,10 +856,15 @@ impl Parser {
             callee: Box::new(expression),
             paren: paren.clone(),
             arguments: arguments,
+            optional,
         }));
     }
 
-    fn finish_access_key(self: &mut Self, expression: Expression) -> Result<Expression, String> {
+    fn finish_access_key(
+        self: &mut Self,
+        expression: Expression,
+        optional: bool,
+    ) -> Result<Expression, String> {
         let key = self.parse_expression()?;
         self.consume(
             TokenType::RightSqureBracket,
@@ -552,6 +874,23 @@ impl Parser {
             target: Box::new(expression),
             left_bracket: self.previous().clone(),
             key: Box::new(key),
+            optional,
+        }));
+    }
+
+    /// `a?.name` is a shorthand for `a?["name"]`.
+    fn finish_access_name(self: &mut Self, expression: Expression) -> Result<Expression, String> {
+        let name = self.consume(
+            TokenType::Identifier,
+            "Expect a name or ( after ?.".to_owned(),
+        )?;
+        return Ok(Expression::KeyAccess(KeyAccessExpression {
+            target: Box::new(expression),
+            left_bracket: name.clone(),
+            key: Box::new(Expression::Literal(LiteralExpression {
+                value: Object::String(name.lexeme),
+            })),
+            optional: true,
         }));
     }
 
@@ -579,6 +918,14 @@ impl Parser {
             }));
         }
 
+        if self.match_token_types(&[TokenType::Interpolation]) {
+            return self.parse_template();
+        }
+
+        if self.match_token_types(&[TokenType::Match]) {
+            return self.parse_match();
+        }
+
         if self.match_token_types(&[TokenType::Identifier]) {
             return Ok(Expression::Variable(VariableExpression {
                 token: self.previous().clone(),
@@ -605,21 +952,168 @@ impl Parser {
         if self.match_token_types(&[TokenType::LeftBrace]) {
             return self.parse_table();
         }
+        if self.match_token_types(&[TokenType::LeftSquareBracket]) {
+            return self.parse_list();
+        }
 
         return Err(format!("Unexpected token"));
     }
 
+    fn parse_match(self: &mut Self) -> Result<Expression, String> {
+        let keyword = self.previous().clone();
+        self.consume(TokenType::LeftParen, "Expect ( after match.".to_string())?;
+        let value = self.parse_expression()?;
+        self.consume(
+            TokenType::RightParen,
+            "Expect ) after expression".to_string(),
+        )?;
+        self.consume(
+            TokenType::LeftBrace,
+            "Expect { after match value".to_string(),
+        )?;
+
+        let mut arms: Vec<MatchArm> = Vec::new();
+        while !self.check(&TokenType::RightBrace) && !self.is_at_end() {
+            let mut patterns = vec![self.parse_pattern()?];
+            while self.match_token_types(&[TokenType::Comma]) {
+                patterns.push(self.parse_pattern()?);
+            }
+            self.consume(TokenType::FatArrow, "Expect => after pattern".to_string())?;
+
+            // Every alternative has to bind the same names, so the body can use them
+            // whichever one matched
+            let names = Self::pattern_names(&patterns[0])?;
+            for pattern in patterns[1..].iter() {
+                if Self::pattern_names(pattern)? != names {
+                    return Err("Alternatives in a match arm must bind the same names".to_string());
+                }
+            }
+            self.begin_scope();
+            for name in patterns[0].bindings() {
+                self.declare(name, false)?;
+            }
+            let body = if self.match_token_types(&[TokenType::LeftBrace]) {
+                MatchBody::Block(Box::new(self.parse_block_statement()?))
+            } else {
+                MatchBody::Expression(Box::new(self.parse_expression()?))
+            };
+            self.end_scope();
+
+            // The comma after a block is optional
+            let is_block = matches!(body, MatchBody::Block(_));
+            arms.push(MatchArm { patterns, body });
+            if !self.match_token_types(&[TokenType::Comma]) && !is_block {
+                break;
+            }
+        }
+        self.consume(
+            TokenType::RightBrace,
+            "Expect } after match arms".to_string(),
+        )?;
+
+        return Ok(Expression::Match(MatchExpression {
+            keyword,
+            value: Box::new(value),
+            arms,
+        }));
+    }
+
+    /// Names bound by `pattern`. A name can only be bound once.
+    fn pattern_names(pattern: &Pattern) -> Result<HashSet<String>, String> {
+        let mut names = HashSet::new();
+        for name in pattern.bindings() {
+            if !names.insert(name.lexeme.clone()) {
+                return Err(format!(
+                    "{} is bound more than once in a pattern",
+                    name.lexeme
+                ));
+            }
+        }
+        return Ok(names);
+    }
+
+    fn parse_pattern(self: &mut Self) -> Result<Pattern, String> {
+        if self.match_token_types(&[TokenType::False]) {
+            return Ok(Pattern::Literal(Object::Bool(false)));
+        }
+        if self.match_token_types(&[TokenType::True]) {
+            return Ok(Pattern::Literal(Object::Bool(true)));
+        }
+        if self.match_token_types(&[TokenType::Nil]) {
+            return Ok(Pattern::Literal(Object::Nil));
+        }
+        if self.match_token_types(&[TokenType::Number, TokenType::String]) {
+            return Ok(Pattern::Literal(self.previous().litral.clone()));
+        }
+        if self.match_token_types(&[TokenType::Minus]) {
+            let number = self.consume(TokenType::Number, "Expect a number after -".to_string())?;
+            match number.litral {
+                Object::Int(n) => return Ok(Pattern::Literal(Object::Int(-n))),
+                Object::Number(n) => return Ok(Pattern::Literal(Object::Number(-n))),
+                _ => return Err("Expect a number after -".to_string()),
+            }
+        }
+        if self.match_token_types(&[TokenType::Identifier]) {
+            let name = self.previous().clone();
+            if name.lexeme == "_" {
+                return Ok(Pattern::Wildcard);
+            }
+            return Ok(Pattern::Binding(name));
+        }
+        if self.match_token_types(&[TokenType::LeftBrace]) {
+            let left_brace = self.previous().clone();
+            let mut entries: Vec<(String, Pattern)> = Vec::new();
+            while !self.check(&TokenType::RightBrace) {
+                let key = self.consume(TokenType::String, "Expect string as a key".to_string())?;
+                self.consume(TokenType::Colon, "Expect ':' after key".to_string())?;
+                entries.push((key.litral.to_string(), self.parse_pattern()?));
+                // The comma after the last entry is optional
+                if !self.match_token_types(&[TokenType::Comma]) {
+                    break;
+                }
+            }
+            self.consume(
+                TokenType::RightBrace,
+                "Expect '}' at the end of the table pattern".to_string(),
+            )?;
+            return Ok(Pattern::Table(left_brace, entries));
+        }
+        return Err("Expect a pattern".to_string());
+    }
+
+    /// Parses the rest of a template string after its first `Interpolation` token.
+    fn parse_template(self: &mut Self) -> Result<Expression, String> {
+        let mut parts: Vec<Expression> = Vec::new();
+        loop {
+            let text = self.previous().litral.clone();
+            if !text.to_string().is_empty() {
+                parts.push(Expression::Literal(LiteralExpression { value: text }));
+            }
+            if self.previous().token_type == TokenType::String {
+                break;
+            }
+            parts.push(self.parse_expression()?);
+            if !self.match_token_types(&[TokenType::Interpolation, TokenType::String]) {
+                return Err("Expect '}' after interpolated expression".to_owned());
+            }
+        }
+        return Ok(Expression::Template(TemplateExpression { parts }));
+    }
+
     fn parse_table(self: &mut Self) -> Result<Expression, String> {
-        let mut map: HashMap<String, Expression> = HashMap::new();
+        let mut values: Vec<(String, Expression)> = Vec::new();
 
         while !self.check(&TokenType::RightBrace) && !self.is_at_end() {
             let key = self.consume(TokenType::String, "Expect string as a key".to_owned())?;
             self.consume(TokenType::Colon, "Expect ':' after key".to_owned())?;
 
             let expr = self.parse_expression().clone()?;
-            self.consume(TokenType::Comma, "Expect ',' after value".to_owned())?;
+            values.push((key.litral.to_string(), expr));
 
-            map.insert(key.litral.to_string(), expr);
+            // The comma after the last entry is optional
+            if !self.match_token_types(&[TokenType::Comma]) {
+                break;
+            }
         }
 
         self.consume(
@@ -627,7 +1121,25 @@ impl Parser {
             "Expect '}' at the end of the table".to_owned(),
         )?;
 
-        return Ok(Expression::Table(TableExpression { values: map }));
+        return Ok(Expression::Table(TableExpression { values }));
+    }
+
+    fn parse_list(self: &mut Self) -> Result<Expression, String> {
+        let mut values: Vec<Expression> = Vec::new();
+
+        while !self.check(&TokenType::RightSqureBracket) && !self.is_at_end() {
+            values.push(self.parse_expression()?);
+            if !self.match_token_types(&[TokenType::Comma]) {
+                break;
+            }
+        }
+
+        self.consume(
+            TokenType::RightSqureBracket,
+            "Expect ']' at the end of the list".to_owned(),
+        )?;
+
+        return Ok(Expression::List(ListExpression { values }));
     }
 
     fn parse_comma_operator(self: &mut Self) -> Result<Expression, String> {
@@ -653,6 +1165,7 @@ impl Parser {
         return Err(message);
     }
 
+    #[allow(dead_code)]
     fn synchronize(self: &mut Self) {
         self.advance();
 
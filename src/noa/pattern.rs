@@ -0,0 +1,80 @@
+use crate::noa::{
+    error::{NoaError, NoaTermination},
+    token::Token,
+    types::Object,
+};
+
+/// Left-hand side of a `match` arm.
+#[derive(Debug, Clone)]
+pub enum Pattern {
+    /// A number, string, bool or nil, compared with `==`.
+    Literal(Object),
+    /// `_` matches anything and binds nothing.
+    Wildcard,
+    /// A name matches anything and binds the value to it.
+    Binding(Token),
+    /// `{ "key": pattern }` matches tables holding every listed key, with each value
+    /// matching its pattern. Other keys are ignored.
+    Table(Token, Vec<(String, Pattern)>),
+}
+
+impl Pattern {
+    /// Tests `value` against the pattern, pushing the names it binds onto `bindings`.
+    pub fn matches(
+        self: &Self,
+        value: &Object,
+        bindings: &mut Vec<(String, Object)>,
+    ) -> Result<bool, NoaTermination> {
+        match self {
+            Pattern::Literal(literal) => return Ok(literal.is_equal(value)),
+            Pattern::Wildcard => return Ok(true),
+            Pattern::Binding(name) => {
+                bindings.push((name.lexeme.clone(), value.clone()));
+                return Ok(true);
+            }
+            Pattern::Table(left_brace, entries) => {
+                let table = match value {
+                    Object::Table(table) => table,
+                    _ => return Ok(false),
+                };
+                // Read the values first, so nested patterns don't run with the table locked
+                let values: Vec<Object> = match table.lock() {
+                    Ok(table) => entries
+                        .iter()
+                        .map(|(key, _)| table.get_value(key.clone()))
+                        .collect(),
+                    Err(_) => {
+                        return Err(NoaTermination::Error(NoaError {
+                            line: left_brace.line,
+                            location: left_brace.lexeme.clone(),
+                            message: format!("Failed to lock table"),
+                        }));
+                    }
+                };
+                for ((_, pattern), value) in entries.iter().zip(values.iter()) {
+                    if let Object::Nil = value {
+                        return Ok(false);
+                    }
+                    if !pattern.matches(value, bindings)? {
+                        return Ok(false);
+                    }
+                }
+                return Ok(true);
+            }
+        }
+    }
+
+    /// Names bound by the pattern, in order.
+    pub fn bindings(self: &Self) -> Vec<&Token> {
+        match self {
+            Pattern::Binding(name) => return vec![name],
+            Pattern::Table(_, entries) => {
+                return entries
+                    .iter()
+                    .flat_map(|(_, pattern)| pattern.bindings())
+                    .collect();
+            }
+            Pattern::Literal(_) | Pattern::Wildcard => return Vec::new(),
+        }
+    }
+}
@@ -0,0 +1,172 @@
+use std::{
+    sync::{Arc, Mutex},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use crate::noa::{
+    error::{NoaError, NoaTermination},
+    generic::get_list_argument,
+    number::get_integer_argument,
+    types::{NativeCallback, Object},
+};
+
+/// xoshiro256** generator. Small, fast and reproducible across platforms,
+/// which is all scripts need; it is not suitable for cryptography.
+pub struct Rng {
+    state: [u64; 4],
+}
+
+fn splitmix64(seed: &mut u64) -> u64 {
+    *seed = seed.wrapping_add(0x9E3779B97F4A7C15);
+    let mut z = *seed;
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58476D1CE4E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D049BB133111EB);
+    return z ^ (z >> 31);
+}
+
+impl Rng {
+    pub fn new(seed: u64) -> Self {
+        let mut rng = Rng { state: [0; 4] };
+        rng.seed(seed);
+        return rng;
+    }
+    /// Seeds from the system time, for runs that don't ask to be reproducible.
+    pub fn from_time() -> Self {
+        let nanos = match SystemTime::now().duration_since(UNIX_EPOCH) {
+            Ok(duration) => duration.as_nanos() as u64,
+            Err(_) => 0,
+        };
+        return Self::new(nanos);
+    }
+    pub fn seed(self: &mut Self, seed: u64) {
+        let mut seed = seed;
+        for word in self.state.iter_mut() {
+            *word = splitmix64(&mut seed);
+        }
+    }
+    pub fn next_u64(self: &mut Self) -> u64 {
+        let result = self.state[1].wrapping_mul(5).rotate_left(7).wrapping_mul(9);
+        let t = self.state[1] << 17;
+        self.state[2] ^= self.state[0];
+        self.state[3] ^= self.state[1];
+        self.state[1] ^= self.state[2];
+        self.state[0] ^= self.state[3];
+        self.state[2] ^= t;
+        self.state[3] = self.state[3].rotate_left(45);
+        return result;
+    }
+    /// Uniform float in `[0, 1)`.
+    pub fn next_float(self: &mut Self) -> f64 {
+        return (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64;
+    }
+    /// Uniform integer in `[0, bound)`, without modulo bias.
+    pub fn next_below(self: &mut Self, bound: u64) -> u64 {
+        let zone = u64::MAX - u64::MAX % bound;
+        loop {
+            let value = self.next_u64();
+            if value < zone {
+                return value % bound;
+            }
+        }
+    }
+}
+
+pub type SharedRng = Arc<Mutex<Rng>>;
+
+fn random_error(function: &str, message: String) -> NoaTermination {
+    return NoaTermination::Error(NoaError {
+        line: 0,
+        location: function.to_owned(),
+        message,
+    });
+}
+
+fn with_rng<T>(
+    rng: &SharedRng,
+    function: &str,
+    operation: impl FnOnce(&mut Rng) -> T,
+) -> Result<T, NoaTermination> {
+    match rng.lock() {
+        Ok(mut rng) => Ok(operation(&mut rng)),
+        Err(_) => Err(random_error(
+            function,
+            "Failed to lock random generator".to_owned(),
+        )),
+    }
+}
+
+pub fn random(rng: SharedRng) -> NativeCallback {
+    return Arc::new(move |_, _| with_rng(&rng, "random", |rng| Object::Number(rng.next_float())));
+}
+
+/// `random_int(lo, hi)` returns an integer between `lo` and `hi`, both included.
+pub fn random_int(rng: SharedRng) -> NativeCallback {
+    return Arc::new(move |arguments, _| {
+        let lo = get_integer_argument(arguments, 0, "random_int")?;
+        let hi = get_integer_argument(arguments, 1, "random_int")?;
+        if lo > hi {
+            return Err(random_error(
+                "random_int",
+                format!("random_int expects lo <= hi, got {} and {}", lo, hi),
+            ));
+        }
+        let span = hi.wrapping_sub(lo) as u64;
+        with_rng(&rng, "random_int", |rng| {
+            let offset = match span.checked_add(1) {
+                Some(bound) => rng.next_below(bound),
+                None => rng.next_u64(),
+            };
+            Object::Int(lo.wrapping_add(offset as i64))
+        })
+    });
+}
+
+/// `random_choice(list)` returns a random item, or nil for an empty list.
+pub fn random_choice(rng: SharedRng) -> NativeCallback {
+    return Arc::new(move |arguments, _| {
+        let list = get_list_argument(arguments, 0, "random_choice")?;
+        let list = match list.lock() {
+            Ok(list) => list,
+            Err(_) => {
+                return Err(random_error(
+                    "random_choice",
+                    "Failed to lock list".to_owned(),
+                ));
+            }
+        };
+        if list.is_empty() {
+            return Ok(Object::Nil);
+        }
+        let index = with_rng(&rng, "random_choice", |rng| {
+            rng.next_below(list.len() as u64)
+        })?;
+        Ok(list[index as usize].clone())
+    });
+}
+
+/// `shuffle(list)` shuffles the list in place.
+pub fn shuffle(rng: SharedRng) -> NativeCallback {
+    return Arc::new(move |arguments, _| {
+        let list = get_list_argument(arguments, 0, "shuffle")?;
+        let mut list = match list.lock() {
+            Ok(list) => list,
+            Err(_) => return Err(random_error("shuffle", "Failed to lock list".to_owned())),
+        };
+        with_rng(&rng, "shuffle", |rng| {
+            for i in (1..list.len()).rev() {
+                let j = rng.next_below(i as u64 + 1) as usize;
+                list.swap(i, j);
+            }
+        })?;
+        Ok(Object::Nil)
+    });
+}
+
+/// `seed(n)` restarts the generator so the following numbers repeat between runs.
+pub fn seed(rng: SharedRng) -> NativeCallback {
+    return Arc::new(move |arguments, _| {
+        let seed = get_integer_argument(arguments, 0, "seed")?;
+        with_rng(&rng, "seed", |rng| rng.seed(seed as u64))?;
+        Ok(Object::Nil)
+    });
+}
@@ -12,9 +12,113 @@ pub struct Scanner {
     keywords: HashMap<String, TokenType>,
     source: String,
     pub tokens: Vec<Token>,
+    /// Byte offsets into `source`, always on character boundaries.
     start: usize,
     current: usize,
     line: usize,
+    /// One entry per `${` we are inside of.
+    interpolations: Vec<Interpolation>,
+    /// Pieces of the triple-quoted strings being scanned, as token index and raw text.
+    triple_strings: Vec<Vec<(usize, String)>>,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum StringKind {
+    Normal,
+    /// `"""..."""`, which may span lines and is dedented.
+    Triple,
+}
+
+struct Interpolation {
+    /// Kind of the string to resume once the expression is closed.
+    kind: StringKind,
+    /// Braces opened inside the expression that are still open.
+    depth: usize,
+}
+
+/// Processes the escape sequences in a string literal.
+fn unescape(raw: &str) -> Result<String, String> {
+    let mut value = String::new();
+    let mut chars = raw.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            value.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n') => value.push('\n'),
+            Some('t') => value.push('\t'),
+            Some('r') => value.push('\r'),
+            Some('0') => value.push('\0'),
+            Some('\\') => value.push('\\'),
+            Some('"') => value.push('"'),
+            Some('\'') => value.push('\''),
+            Some('$') => value.push('$'),
+            Some('u') => {
+                if chars.next() != Some('{') {
+                    return Err("Expect '{' after \\u".to_owned());
+                }
+                let mut hex = String::new();
+                loop {
+                    match chars.next() {
+                        Some('}') => break,
+                        Some(c) if c.is_ascii_hexdigit() && hex.len() < 6 => hex.push(c),
+                        _ => return Err("Invalid \\u{...} escape".to_owned()),
+                    }
+                }
+                match u32::from_str_radix(&hex, 16).ok().and_then(char::from_u32) {
+                    Some(c) => value.push(c),
+                    None => {
+                        return Err(format!("\\u{{{}}} is not a valid Unicode code point", hex));
+                    }
+                }
+            }
+            Some(other) => return Err(format!("Unknown escape sequence '\\{}'", other)),
+            None => return Err("Unterminated escape sequence".to_owned()),
+        }
+    }
+    return Ok(value);
+}
+
+/// Dedents the raw pieces of a triple-quoted string. A line break right after the
+/// opening quotes and a last line holding only the closing quotes' indentation are
+/// dropped, then the indentation common to all lines is removed. Interpolated
+/// expressions sit between the pieces and count as text.
+fn dedent(pieces: &[&str]) -> Vec<String> {
+    const MARKER: char = '\u{0}';
+    let mut text = pieces.join(&MARKER.to_string());
+    if text.starts_with("\r\n") {
+        text.drain(..2);
+    } else if text.starts_with('\n') {
+        text.drain(..1);
+    }
+    let mut lines: Vec<&str> = text.split('\n').collect();
+    let closing_indent = match lines.last() {
+        Some(last) if lines.len() > 1 && last.chars().all(|c| c == ' ' || c == '\t') => {
+            Some(last.len())
+        }
+        _ => None,
+    };
+    if closing_indent.is_some() {
+        lines.pop();
+    }
+    let indent_of = |line: &str| line.len() - line.trim_start_matches([' ', '\t']).len();
+    let indent = lines
+        .iter()
+        .filter(|line| !line.trim_start_matches([' ', '\t', '\r']).is_empty())
+        .map(|line| indent_of(line))
+        .chain(closing_indent)
+        .min()
+        .unwrap_or(0);
+    let dedented: Vec<&str> = lines
+        .iter()
+        .map(|line| &line[indent.min(indent_of(line))..])
+        .collect();
+    return dedented
+        .join("\n")
+        .split(MARKER)
+        .map(|piece| piece.to_owned())
+        .collect();
 }
 
 impl Scanner {
@@ -22,11 +126,13 @@ impl Scanner {
         let mut keywords: HashMap<String, TokenType> = HashMap::new();
         keywords.insert(String::from("and"), TokenType::And);
         keywords.insert(String::from("class"), TokenType::Class);
+        keywords.insert(String::from("const"), TokenType::Const);
         keywords.insert(String::from("else"), TokenType::Else);
         keywords.insert(String::from("false"), TokenType::False);
         keywords.insert(String::from("for"), TokenType::For);
         keywords.insert(String::from("fn"), TokenType::Fn);
         keywords.insert(String::from("if"), TokenType::If);
+        keywords.insert(String::from("in"), TokenType::In);
         keywords.insert(String::from("nil"), TokenType::Nil);
         keywords.insert(String::from("or"), TokenType::Or);
         keywords.insert(String::from("return"), TokenType::Return);
@@ -34,6 +140,7 @@ impl Scanner {
         keywords.insert(String::from("this"), TokenType::This);
         keywords.insert(String::from("true"), TokenType::True);
         keywords.insert(String::from("let"), TokenType::Let);
+        keywords.insert(String::from("match"), TokenType::Match);
         keywords.insert(String::from("while"), TokenType::While);
 
         return Scanner {
@@ -43,13 +150,16 @@ impl Scanner {
             start: 0,
             current: 0,
             line: 1,
+            interpolations: Vec::new(),
+            triple_strings: Vec::new(),
         };
     }
 
+    #[allow(dead_code)]
     pub fn debug_print(self: &Self) {
         println!("---Tokens Start---");
         for (i, token) in self.tokens.iter().enumerate() {
-            if token.lexeme.len() > 0 {
+            if !token.lexeme.is_empty() {
                 println!("{}: {} {:?}", i, token.lexeme, token.token_type)
             } else {
                 println!("{}: {:?}", i, token.token_type)
@@ -73,6 +183,13 @@ impl Scanner {
                 });
             }
         }
+        if !self.interpolations.is_empty() {
+            return Err(NoaError {
+                line: self.line,
+                location: "${".to_owned(),
+                message: "Unterminated string interpolation".to_owned(),
+            });
+        }
         self.tokens.push(Token {
             token_type: TokenType::EOF,
             line: self.line,
@@ -82,21 +199,130 @@ impl Scanner {
         return Ok(());
     }
 
-    fn scan_string_literal(self: &mut Self) -> Result<(), String> {
-        while self.peek() != '"' && !self.is_at_end() {
-            if self.peek() == '\n' {
-                self.line += 1;
+    /// Scans string contents up to the closing quote. The scan starts right after the
+    /// opening quote, or after the `}` closing an interpolated expression.
+    /// `"a ${x} b"` becomes `Interpolation("a ")`, the tokens of `x`, then `String(" b")`.
+    fn scan_string_literal(self: &mut Self, kind: StringKind) -> Result<(), String> {
+        let content_start = self.current;
+        loop {
+            if self.is_at_end() {
+                return Err("Unterminated string".to_string());
+            }
+            let closed = match kind {
+                StringKind::Normal => self.peek() == '"',
+                StringKind::Triple => {
+                    self.peek() == '"' && self.peek_next() == '"' && self.peek_at(2) == '"'
+                }
+            };
+            if closed {
+                break;
             }
+            match self.peek() {
+                '$' if self.peek_next() == '{' => {
+                    let raw = String::from(&self.source[content_start..self.current]);
+                    self.advance();
+                    self.advance();
+                    self.add_string_piece(TokenType::Interpolation, kind, raw)?;
+                    self.interpolations.push(Interpolation { kind, depth: 0 });
+                    return Ok(());
+                }
+                // Skip the escaped character so \" and \$ don't end the string
+                '\\' => {
+                    self.advance();
+                    if self.peek() == '\n' {
+                        self.line += 1;
+                    }
+                    self.advance();
+                }
+                '\n' => {
+                    self.line += 1;
+                    self.advance();
+                }
+                _ => {
+                    self.advance();
+                }
+            }
+        }
 
+        let raw = String::from(&self.source[content_start..self.current]);
+        let quotes = match kind {
+            StringKind::Normal => 1,
+            StringKind::Triple => 3,
+        };
+        for _ in 0..quotes {
             self.advance();
         }
+        self.add_string_piece(TokenType::String, kind, raw)?;
+        if let StringKind::Triple = kind {
+            self.finish_triple_string()?;
+        }
+        Ok(())
+    }
 
-        if self.is_at_end() {
-            return Err("Unterminated string".to_string());
+    /// Adds one piece of a string. Pieces of triple-quoted strings can only be unescaped
+    /// once the whole string is known, because dedenting looks at every line.
+    fn add_string_piece(
+        self: &mut Self,
+        token_type: TokenType,
+        kind: StringKind,
+        raw: String,
+    ) -> Result<(), String> {
+        match kind {
+            StringKind::Normal => {
+                let value = unescape(&raw)?;
+                self.add_token_with_literal(token_type, Object::String(value));
+            }
+            StringKind::Triple => {
+                self.add_token_with_literal(token_type, Object::Nil);
+                let index = self.tokens.len() - 1;
+                match self.triple_strings.last_mut() {
+                    Some(pieces) => pieces.push((index, raw)),
+                    None => return Err("Triple-quoted string piece outside of a string".to_owned()),
+                }
+            }
         }
-        self.advance();
-        let value = &self.source[self.start + 1..self.current - 1];
-        self.add_token_with_literal(TokenType::String, Object::String(String::from(value)));
+        Ok(())
+    }
+
+    fn finish_triple_string(self: &mut Self) -> Result<(), String> {
+        let pieces = match self.triple_strings.pop() {
+            Some(pieces) => pieces,
+            None => return Ok(()),
+        };
+        let raw: Vec<&str> = pieces.iter().map(|(_, raw)| raw.as_str()).collect();
+        for ((index, _), text) in pieces.iter().zip(dedent(&raw)) {
+            self.tokens[*index].litral = Object::String(unescape(&text)?);
+        }
+        Ok(())
+    }
+
+    /// Raw strings: `r"C:\dir"` or `r#"say "hi""#`. Nothing inside them is special.
+    fn scan_raw_string_literal(self: &mut Self) -> Result<(), String> {
+        let mut hashes = 0;
+        while self.match_next_char('#') {
+            hashes += 1;
+        }
+        if !self.match_next_char('"') {
+            return Err("Expect '\"' to start raw string".to_owned());
+        }
+        let content_start = self.current;
+        loop {
+            if self.is_at_end() {
+                return Err("Unterminated raw string".to_owned());
+            }
+            if self.peek() == '"' && (1..=hashes).all(|offset| self.peek_at(offset) == '#') {
+                break;
+            }
+            if self.peek() == '\n' {
+                self.line += 1;
+            }
+            self.advance();
+        }
+        let value = String::from(&self.source[content_start..self.current]);
+        for _ in 0..hashes + 1 {
+            self.advance();
+        }
+        self.add_token_with_literal(TokenType::String, Object::String(value));
         Ok(())
     }
 
@@ -108,8 +334,8 @@ impl Scanner {
         let token_type: TokenType = self
             .keywords
             .get(value)
-            .unwrap_or(&TokenType::Identifier)
-            .clone();
+            .copied()
+            .unwrap_or(TokenType::Identifier);
         self.add_token_with_literal(token_type, Object::String(String::from(value)));
         Ok(())
     }
@@ -122,7 +348,7 @@ impl Scanner {
     }
 
     fn skip_multi_line_comment(self: &mut Self) -> Result<(), String> {
-        while !(self.peek() == '*' && self.peek_next() == '/') && !self.is_at_end() {
+        while !(self.is_at_end() || self.peek() == '*' && self.peek_next() == '/') {
             if self.peek() == '\n' {
                 self.line += 1;
             }
@@ -150,24 +376,86 @@ impl Scanner {
         Ok(())
     }
 
+    /// Consumes digits of `radix` and `_` separators, returning the digits without separators.
+    /// A separator has to sit between two digits.
+    fn scan_digits(self: &mut Self, radix: u32) -> String {
+        let mut digits = String::new();
+        while self.peek().is_digit(radix)
+            || (self.peek() == '_' && !digits.is_empty() && self.peek_next().is_digit(radix))
+        {
+            let c = self.advance();
+            if c != '_' {
+                digits.push(c);
+            }
+        }
+        return digits;
+    }
+
+    /// Decimal literals like `1_000`, `2.5` and `1e-9`, and `0x`, `0b` and `0o` integers.
     fn scan_number_literal(self: &mut Self) -> Result<(), String> {
-        while self.peek().is_digit(10) {
+        // The first digit was already consumed
+        self.current = self.start;
+        let radix = match (self.peek(), self.peek_next()) {
+            ('0', 'x' | 'X') => 16,
+            ('0', 'b' | 'B') => 2,
+            ('0', 'o' | 'O') => 8,
+            _ => 10,
+        };
+
+        let value = if radix != 10 {
             self.advance();
-        }
-        if self.peek() == '.' && self.peek_next().is_digit(10) {
             self.advance();
-            while self.peek().is_digit(10) {
+            let digits = self.scan_digits(radix);
+            if digits.is_empty() {
+                return Err(String::from("Expect digits after number prefix"));
+            }
+            match i64::from_str_radix(&digits, radix) {
+                Ok(v) => Object::Int(v),
+                Err(_) => return Err(String::from("Integer literal is too large")),
+            }
+        } else {
+            let mut text = self.scan_digits(10);
+            let mut is_float = false;
+            if self.peek() == '.' && self.peek_next().is_ascii_digit() {
+                is_float = true;
                 self.advance();
+                text.push('.');
+                text.push_str(&self.scan_digits(10));
             }
-        }
-        let value = &self.source[self.start..self.current];
-        let value: Number = match value.parse() {
-            Ok(v) => v,
-            Err(_) => {
-                return Err(String::from("Failed to parse number"));
+            let sign = self.peek_next();
+            if (self.peek() == 'e' || self.peek() == 'E')
+                && (sign.is_ascii_digit()
+                    || ((sign == '+' || sign == '-') && self.peek_at(2).is_ascii_digit()))
+            {
+                is_float = true;
+                self.advance();
+                text.push('e');
+                if !sign.is_ascii_digit() {
+                    text.push(self.advance());
+                }
+                text.push_str(&self.scan_digits(10));
+            }
+            if is_float {
+                match text.parse::<Number>() {
+                    Ok(v) => Object::Number(v),
+                    Err(_) => {
+                        return Err(String::from("Failed to parse number"));
+                    }
+                }
+            } else {
+                match text.parse::<i64>() {
+                    Ok(v) => Object::Int(v),
+                    Err(_) => return Err(String::from("Integer literal is too large")),
+                }
             }
         };
-        self.add_token_with_literal(TokenType::Number, Object::Number(value));
+        if self.peek() == '_' {
+            return Err(String::from("Digit separator _ must be between two digits"));
+        }
+        if self.peek().is_alphanumeric() {
+            return Err(format!("Invalid character '{}' in number", self.peek()));
+        }
+        self.add_token_with_literal(TokenType::Number, value);
         Ok(())
     }
 
@@ -177,17 +465,86 @@ impl Scanner {
         match c {
             '(' => self.add_token(TokenType::LeftParen),
             ')' => self.add_token(TokenType::RightParen),
-            '{' => self.add_token(TokenType::LeftBrace),
-            '}' => self.add_token(TokenType::RightBrace),
+            '{' => {
+                if let Some(interpolation) = self.interpolations.last_mut() {
+                    interpolation.depth += 1;
+                }
+                self.add_token(TokenType::LeftBrace);
+            }
+            '}' => match self.interpolations.last_mut() {
+                // This closes an interpolated expression, the string continues
+                Some(Interpolation { kind, depth: 0 }) => {
+                    let kind = *kind;
+                    self.interpolations.pop();
+                    self.scan_string_literal(kind)?;
+                }
+                Some(interpolation) => {
+                    interpolation.depth -= 1;
+                    self.add_token(TokenType::RightBrace);
+                }
+                None => self.add_token(TokenType::RightBrace),
+            },
             '[' => self.add_token(TokenType::LeftSquareBracket),
             ']' => self.add_token(TokenType::RightSqureBracket),
             ',' => self.add_token(TokenType::Comma),
             '.' => self.add_token(TokenType::Dot),
-            '-' => self.add_token(TokenType::Minus),
-            '+' => self.add_token(TokenType::Plus),
+            '-' => {
+                if self.match_next_char('-') {
+                    self.add_token(TokenType::MinusMinus);
+                } else if self.match_next_char('=') {
+                    self.add_token(TokenType::MinusEqual);
+                } else {
+                    self.add_token(TokenType::Minus);
+                }
+            }
+            '+' => {
+                if self.match_next_char('+') {
+                    self.add_token(TokenType::PlusPlus);
+                } else if self.match_next_char('=') {
+                    self.add_token(TokenType::PlusEqual);
+                } else {
+                    self.add_token(TokenType::Plus);
+                }
+            }
             ';' => self.add_token(TokenType::Semicolon),
-            '*' => self.add_token(TokenType::Star),
-            '?' => self.add_token(TokenType::QuestionMark),
+            '*' => {
+                if self.match_next_char('*') {
+                    self.add_token(TokenType::StarStar);
+                } else if self.match_next_char('=') {
+                    self.add_token(TokenType::StarEqual);
+                } else {
+                    self.add_token(TokenType::Star);
+                }
+            }
+            '%' => {
+                if self.match_next_char('=') {
+                    self.add_token(TokenType::PercentEqual);
+                } else {
+                    self.add_token(TokenType::Percent);
+                }
+            }
+            '~' => {
+                if self.match_next_char('/') {
+                    self.add_token(TokenType::TildeSlash);
+                } else {
+                    self.add_token(TokenType::Tilde);
+                }
+            }
+            '&' => self.add_token(TokenType::Ampersand),
+            '|' => self.add_token(TokenType::Pipe),
+            '^' => self.add_token(TokenType::Caret),
+            '?' => {
+                if self.match_next_char('?') {
+                    self.add_token(TokenType::QuestionQuestion);
+                } else if self.match_next_char('.') {
+                    self.add_token(TokenType::QuestionDot);
+                } else if self.peek() == '[' && self.follows_operand() {
+                    self.advance();
+                    self.add_token(TokenType::QuestionBracket);
+                } else {
+                    self.add_token(TokenType::QuestionMark);
+                }
+            }
             ':' => self.add_token(TokenType::Colon),
             '!' => {
                 if self.match_next_char('=') {
@@ -199,6 +556,8 @@ impl Scanner {
             '=' => {
                 if self.match_next_char('=') {
                     self.add_token(TokenType::EqualEqual);
+                } else if self.match_next_char('>') {
+                    self.add_token(TokenType::FatArrow);
                 } else {
                     self.add_token(TokenType::Equal)
                 }
@@ -206,6 +565,8 @@ impl Scanner {
             '<' => {
                 if self.match_next_char('=') {
                     self.add_token(TokenType::LessEqual);
+                } else if self.match_next_char('<') {
+                    self.add_token(TokenType::LessLess);
                 } else {
                     self.add_token(TokenType::Less);
                 }
@@ -213,6 +574,8 @@ impl Scanner {
             '>' => {
                 if self.match_next_char('=') {
                     self.add_token(TokenType::GreaterEqual);
+                } else if self.match_next_char('>') {
+                    self.add_token(TokenType::GreaterGreater);
                 } else {
                     self.add_token(TokenType::Greater);
                 }
@@ -222,6 +585,8 @@ impl Scanner {
                     self.skip_single_line_comment()?;
                 } else if self.match_next_char('*') {
                     self.skip_multi_line_comment()?;
+                } else if self.match_next_char('=') {
+                    self.add_token(TokenType::SlashEqual);
                 } else {
                     self.add_token(TokenType::Slash);
                 }
@@ -230,10 +595,20 @@ impl Scanner {
             '\n' => {
                 self.line += 1;
             }
-            '"' => self.scan_string_literal()?,
+            '"' => {
+                if self.peek() == '"' && self.peek_next() == '"' {
+                    self.advance();
+                    self.advance();
+                    self.triple_strings.push(Vec::new());
+                    self.scan_string_literal(StringKind::Triple)?;
+                } else {
+                    self.scan_string_literal(StringKind::Normal)?;
+                }
+            }
+            'r' if self.peek() == '"' || self.peek() == '#' => self.scan_raw_string_literal()?,
 
             _ => {
-                if c.is_digit(10) {
+                if c.is_ascii_digit() {
                     self.scan_number_literal()?;
                 } else if c.is_alphanumeric() || c == '_' {
                     self.scan_identifier_token()?;
@@ -255,31 +630,59 @@ impl Scanner {
         });
     }
 
+    /// Whether the current token is written right after something that can end an
+    /// operand, as in `a?[k]`. Otherwise `c ?[1] : [2]` is a ternary.
+    fn follows_operand(self: &Self) -> bool {
+        let touching = match self.source[..self.start].chars().next_back() {
+            Some(c) => !c.is_whitespace(),
+            None => false,
+        };
+        let operand = match self.tokens.last() {
+            Some(token) => matches!(
+                token.token_type,
+                TokenType::Identifier
+                    | TokenType::String
+                    | TokenType::Number
+                    | TokenType::True
+                    | TokenType::False
+                    | TokenType::Nil
+                    | TokenType::RightParen
+                    | TokenType::RightSqureBracket
+                    | TokenType::RightBrace
+            ),
+            None => false,
+        };
+        return touching && operand;
+    }
+
     fn add_token(self: &mut Self, token_type: TokenType) {
         self.add_token_with_literal(token_type, Object::Nil);
     }
 
     fn advance(self: &mut Self) -> char {
-        self.current += 1;
+        match self.source[self.current..].chars().next() {
+            Some(c) => {
+                self.current += c.len_utf8();
+                return c;
+            }
+            None => return '\0',
+        }
+    }
 
-        return self.source.chars().nth(self.current - 1).unwrap_or('\0');
+    fn peek(self: &Self) -> char {
+        return self.peek_at(0);
     }
 
-    fn peek(self: &mut Self) -> char {
-        if self.is_at_end() {
-            return '\0';
-        }
-        return self.source.chars().nth(self.current).unwrap_or('\0');
+    fn peek_next(self: &Self) -> char {
+        return self.peek_at(1);
     }
 
-    fn peek_next(self: &mut Self) -> char {
-        if self.is_at_end() {
-            return '\0';
-        }
-        if self.current + 1 >= self.source.len() {
-            return '\0';
-        }
-        return self.source.chars().nth(self.current + 1).unwrap_or('\0');
+    /// The character `offset` characters after the current one.
+    fn peek_at(self: &Self, offset: usize) -> char {
+        return self.source[self.current..]
+            .chars()
+            .nth(offset)
+            .unwrap_or('\0');
     }
 
     fn match_next_char(self: &mut Self, expected: char) -> bool {
@@ -287,10 +690,10 @@ impl Scanner {
             return false;
         }
 
-        if self.source.chars().nth(self.current).unwrap_or('\0') != expected {
+        if self.peek() != expected {
             return false;
         }
-        self.current += 1;
+        self.current += expected.len_utf8();
         return true;
     }
 }
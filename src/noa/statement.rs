@@ -15,6 +15,7 @@ pub enum Statement {
     Block(BlockStatement),
     If(IfStatement),
     While(WhileStatement),
+    ForIn(ForInStatement),
     Function(FunctionStatement),
     Return(ReturnStatement),
 }
@@ -35,6 +36,14 @@ pub struct WhileStatement {
     pub if_true: Box<Statement>,
 }
 #[derive(Debug, Clone)]
+pub struct ForInStatement {
+    pub keyword: Token,
+    /// One or two names, e.g. `item` or `key, value`.
+    pub variables: Vec<Token>,
+    pub iterable: Box<Expression>,
+    pub body: Box<Statement>,
+}
+#[derive(Debug, Clone)]
 
 pub struct IfStatement {
     pub check: Box<Expression>,
@@ -56,6 +65,156 @@ pub struct ExpressionStatement {
 pub struct VarStatement {
     pub initializer: Option<Box<Expression>>,
     pub identifier: Token,
+    /// Declared with `const`, the variable can't be assigned to afterwards.
+    pub constant: bool,
+}
+
+impl ForInStatement {
+    fn error(self: &Self, message: String) -> NoaTermination {
+        return NoaTermination::Error(NoaError {
+            line: self.keyword.line,
+            location: self.keyword.lexeme.clone(),
+            message,
+        });
+    }
+
+    /// Runs the body once in a fresh scope with the loop variables bound to `values`.
+    fn run_body(
+        self: &Self,
+        values: Vec<Object>,
+        environment: Arc<Mutex<Environment>>,
+    ) -> Result<(), NoaTermination> {
+        let mut scope = Environment::new();
+        scope.enclose(environment);
+        for (variable, value) in self.variables.iter().zip(values) {
+            scope.define(variable.lexeme.clone(), value);
+        }
+        return self.body.execute(Arc::new(Mutex::new(scope)));
+    }
+
+    /// `key` for one variable, `key, value` for two.
+    fn pair(self: &Self, key: Object, value: Object) -> Vec<Object> {
+        if self.variables.len() == 1 {
+            return vec![key];
+        }
+        return vec![key, value];
+    }
+
+    /// `value` for one variable, `index, value` for two.
+    fn indexed(self: &Self, index: usize, value: Object) -> Vec<Object> {
+        if self.variables.len() == 1 {
+            return vec![value];
+        }
+        return vec![Object::Int(index as i64), value];
+    }
+
+    /// Calls `function` with no arguments for the iteration protocol. `name` is `__iter`
+    /// or `next`, errors are reported at the line of the `for`.
+    fn call_protocol(
+        self: &Self,
+        name: &str,
+        function: &Function,
+    ) -> Result<Object, NoaTermination> {
+        if !function.params.is_empty() {
+            return Err(self.error(format!(
+                "{} must take no arguments, takes {}",
+                name,
+                function.params.len()
+            )));
+        }
+        return function
+            .call(Vec::new())
+            .map_err(|termination| match termination {
+                NoaTermination::Error(error) if error.line == 0 => {
+                    NoaTermination::Error(NoaError {
+                        line: self.keyword.line,
+                        ..error
+                    })
+                }
+                _ => termination,
+            });
+    }
+
+    /// Calls `next` until it returns nil. With two variables each step must yield a `[a, b]` list.
+    fn iterate_function(
+        self: &Self,
+        next: &Function,
+        environment: Arc<Mutex<Environment>>,
+    ) -> Result<(), NoaTermination> {
+        loop {
+            let value = self.call_protocol("next", next)?;
+            if let Object::Nil = value {
+                return Ok(());
+            }
+            if self.variables.len() == 1 {
+                self.run_body(vec![value], environment.clone())?;
+                continue;
+            }
+            let values = match &value {
+                Object::List(list) => match list.lock() {
+                    Ok(list) if list.len() == 2 => list.clone(),
+                    Ok(_) => Vec::new(),
+                    Err(_) => return Err(self.error("Failed to lock list".to_owned())),
+                },
+                _ => Vec::new(),
+            };
+            if values.is_empty() {
+                return Err(self.error(format!(
+                    "Iterator must yield a [key, value] list for two loop variables, got {}",
+                    value
+                )));
+            }
+            self.run_body(values, environment.clone())?;
+        }
+    }
+
+    fn execute(self: &Self, environment: Arc<Mutex<Environment>>) -> Result<(), NoaTermination> {
+        let iterable = self.iterable.evaluate(environment.clone())?;
+
+        // Collections are snapshotted, so changing them inside the loop doesn't affect it
+        let items: Vec<Vec<Object>> = match &iterable {
+            Object::Table(table) => {
+                let table = match table.lock() {
+                    Ok(table) => table.clone(),
+                    Err(_) => return Err(self.error("Failed to lock table".to_owned())),
+                };
+                if let Object::Function(iter) = table.get_value("__iter".to_owned()) {
+                    let next = self.call_protocol("__iter", &iter)?;
+                    return match next {
+                        Object::Function(next) => self.iterate_function(&next, environment),
+                        _ => {
+                            Err(self.error(format!("__iter must return a function, got {}", next)))
+                        }
+                    };
+                }
+                table
+                    .values
+                    .into_iter()
+                    .map(|(key, value)| self.pair(Object::String(key), value))
+                    .collect()
+            }
+            Object::List(list) => match list.lock() {
+                Ok(list) => list
+                    .iter()
+                    .enumerate()
+                    .map(|(i, value)| self.indexed(i, value.clone()))
+                    .collect(),
+                Err(_) => return Err(self.error("Failed to lock list".to_owned())),
+            },
+            Object::String(str) => str
+                .chars()
+                .enumerate()
+                .map(|(i, c)| self.indexed(i, Object::String(c.to_string())))
+                .collect(),
+            Object::Function(next) => return self.iterate_function(next, environment),
+            _ => return Err(self.error(format!("Cannot iterate over {}", iterable))),
+        };
+
+        for values in items {
+            self.run_body(values, environment.clone())?;
+        }
+        return Ok(());
+    }
 }
 
 impl Statement {
@@ -75,7 +234,7 @@ impl Statement {
                 }
                 match environment.lock() {
                     Ok(mut mutex) => {
-                        mutex.define(var_statement.identifier.lexeme.clone(), value);
+                        mutex.declare(&var_statement.identifier, value, var_statement.constant)?;
                     }
                     Err(_) => {
                         return Err(NoaTermination::Error(NoaError {
@@ -131,6 +290,9 @@ impl Statement {
 
                 return Ok(());
             }
+            Statement::ForIn(for_in_statement) => {
+                return for_in_statement.execute(environment);
+            }
             Statement::Function(function_statement) => {
                 let params: Vec<String> = function_statement
                     .params
@@ -147,6 +309,7 @@ impl Statement {
                         let func = Object::Function(Box::new(Function {
                             body: Some(function_statement.body.clone()),
                             params: params,
+                            variadic: false,
                             callback: None,
                             environment: snapshot.clone(),
                         }));
@@ -163,7 +326,7 @@ impl Statement {
                                 }));
                             }
                         }
-                        mutex.define(function_statement.name.lexeme.clone(), func)
+                        mutex.declare(&function_statement.name, func, false)?;
                     }
                     Err(_) => {
                         return Err(NoaTermination::Error(NoaError {
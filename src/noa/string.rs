@@ -3,13 +3,34 @@ use std::sync::{Arc, Mutex};
 use crate::noa::{
     environment::Environment,
     error::{NoaError, NoaTermination},
-    types::{Number, Object},
+    generic::get_list_argument,
+    number::get_integer_argument,
+    types::{NativeCallback, Number, Object},
 };
 
 pub fn str(arguments: &Vec<Object>, _: Arc<Mutex<Environment>>) -> Result<Object, NoaTermination> {
     Ok(Object::String(arguments.first().unwrap().to_string()))
 }
 
+pub fn pretty(
+    arguments: &Vec<Object>,
+    _: Arc<Mutex<Environment>>,
+) -> Result<Object, NoaTermination> {
+    let indent = get_integer_argument(arguments, 1, "pretty")?;
+    if indent < 0 {
+        return Err(string_error(
+            "pretty",
+            format!("pretty expects a non-negative indent, got {}", indent),
+        ));
+    }
+    let indent = usize::try_from(indent).ok();
+    check_string_len("pretty", indent)?;
+    match arguments[0].pretty(indent.unwrap_or(0), MAX_STRING_LEN) {
+        Some(pretty) => Ok(Object::String(pretty)),
+        None => Err(string_too_long("pretty")),
+    }
+}
+
 pub fn str_to_num(
     arguments: &Vec<Object>,
     _: Arc<Mutex<Environment>>,
@@ -17,6 +38,9 @@ pub fn str_to_num(
     let first = arguments.first().unwrap();
     match first {
         Object::String(str) => {
+            if let Ok(num) = str.parse::<i64>() {
+                return Ok(Object::Int(num));
+            }
             let num = str.parse::<Number>();
 
             match num {
@@ -33,3 +57,460 @@ pub fn str_to_num(
         }
     }
 }
+
+fn string_error(function: &str, message: String) -> NoaTermination {
+    return NoaTermination::Error(NoaError {
+        line: 0,
+        location: function.to_owned(),
+        message,
+    });
+}
+
+/// Largest string, in bytes, that natives build from a size given by the script.
+const MAX_STRING_LEN: usize = 1 << 26;
+
+fn string_too_long(function: &str) -> NoaTermination {
+    return string_error(
+        function,
+        format!(
+            "{} would build a string longer than {} bytes",
+            function, MAX_STRING_LEN
+        ),
+    );
+}
+
+/// Fails unless a string of `bytes` bytes, `None` meaning it overflowed, is within
+/// `MAX_STRING_LEN`.
+fn check_string_len(function: &str, bytes: Option<usize>) -> Result<(), NoaTermination> {
+    match bytes {
+        Some(bytes) if bytes <= MAX_STRING_LEN => return Ok(()),
+        _ => return Err(string_too_long(function)),
+    }
+}
+
+pub fn get_string_argument(
+    arguments: &Vec<Object>,
+    index: usize,
+    function: &str,
+) -> Result<String, NoaTermination> {
+    match arguments.get(index) {
+        Some(Object::String(str)) => Ok(str.clone()),
+        other => Err(string_error(
+            function,
+            format!(
+                "{} expects a string as argument {}, got \"{}\"",
+                function,
+                index + 1,
+                other.unwrap_or(&Object::Nil)
+            ),
+        )),
+    }
+}
+
+fn get_single_char_argument(
+    arguments: &Vec<Object>,
+    index: usize,
+    function: &str,
+) -> Result<char, NoaTermination> {
+    let str = get_string_argument(arguments, index, function)?;
+    let mut chars = str.chars();
+    match (chars.next(), chars.next()) {
+        (Some(c), None) => Ok(c),
+        _ => Err(string_error(
+            function,
+            format!(
+                "{} expects a single character as argument {}, got \"{}\"",
+                function,
+                index + 1,
+                str
+            ),
+        )),
+    }
+}
+
+fn check_argument_count(
+    arguments: &Vec<Object>,
+    max: usize,
+    function: &str,
+) -> Result<(), NoaTermination> {
+    if arguments.len() > max {
+        return Err(string_error(
+            function,
+            format!(
+                "{} expects at most {} arguments got {} arguments",
+                function,
+                max,
+                arguments.len()
+            ),
+        ));
+    }
+    Ok(())
+}
+
+fn make_list(values: Vec<Object>) -> Object {
+    return Object::List(Arc::new(Mutex::new(values)));
+}
+
+/// Turns a possibly negative character index into one inside `0..=len`.
+fn clamp_index(index: i64, len: usize) -> usize {
+    if index < 0 {
+        return (len as i64 + index).max(0) as usize;
+    }
+    return (index as usize).min(len);
+}
+
+pub fn split(
+    arguments: &Vec<Object>,
+    _: Arc<Mutex<Environment>>,
+) -> Result<Object, NoaTermination> {
+    let str = get_string_argument(arguments, 0, "split")?;
+    let separator = get_string_argument(arguments, 1, "split")?;
+    if separator.is_empty() {
+        return Ok(make_list(
+            str.chars().map(|c| Object::String(c.to_string())).collect(),
+        ));
+    }
+    Ok(make_list(
+        str.split(separator.as_str())
+            .map(|part| Object::String(part.to_owned()))
+            .collect(),
+    ))
+}
+
+pub fn join(arguments: &Vec<Object>, _: Arc<Mutex<Environment>>) -> Result<Object, NoaTermination> {
+    let list = get_list_argument(arguments, 0, "join")?;
+    let separator = get_string_argument(arguments, 1, "join")?;
+    match list.lock() {
+        Ok(list) => Ok(Object::String(
+            list.iter()
+                .map(|value| value.to_string())
+                .collect::<Vec<String>>()
+                .join(&separator),
+        )),
+        Err(_) => Err(string_error("join", "Failed to lock list".to_owned())),
+    }
+}
+
+pub fn substr(
+    arguments: &Vec<Object>,
+    _: Arc<Mutex<Environment>>,
+) -> Result<Object, NoaTermination> {
+    check_argument_count(arguments, 3, "substr")?;
+    let str = get_string_argument(arguments, 0, "substr")?;
+    let len = str.chars().count();
+    let start = clamp_index(get_integer_argument(arguments, 1, "substr")?, len);
+    let end = match arguments.get(2) {
+        Some(_) => clamp_index(get_integer_argument(arguments, 2, "substr")?, len),
+        None => len,
+    };
+    if start >= end {
+        return Ok(Object::String(String::new()));
+    }
+    Ok(Object::String(
+        str.chars().skip(start).take(end - start).collect(),
+    ))
+}
+
+pub fn find(arguments: &Vec<Object>, _: Arc<Mutex<Environment>>) -> Result<Object, NoaTermination> {
+    let str = get_string_argument(arguments, 0, "find")?;
+    let needle = get_string_argument(arguments, 1, "find")?;
+    match str.find(needle.as_str()) {
+        Some(byte_index) => Ok(Object::Int(str[..byte_index].chars().count() as i64)),
+        None => Ok(Object::Nil),
+    }
+}
+
+pub fn replace(
+    arguments: &Vec<Object>,
+    _: Arc<Mutex<Environment>>,
+) -> Result<Object, NoaTermination> {
+    let str = get_string_argument(arguments, 0, "replace")?;
+    let from = get_string_argument(arguments, 1, "replace")?;
+    let to = get_string_argument(arguments, 2, "replace")?;
+    if from.is_empty() {
+        return Err(string_error(
+            "replace",
+            "replace expects a non-empty pattern".to_owned(),
+        ));
+    }
+    Ok(Object::String(str.replace(from.as_str(), &to)))
+}
+
+/// Wraps a `&str -> String` function as a native taking one string.
+pub fn transform(name: &'static str, operation: fn(&str) -> String) -> NativeCallback {
+    return Arc::new(move |arguments, _| {
+        let str = get_string_argument(arguments, 0, name)?;
+        Ok(Object::String(operation(&str)))
+    });
+}
+
+/// Wraps a `(&str, &str) -> bool` function as a native taking two strings.
+pub fn predicate(name: &'static str, operation: fn(&str, &str) -> bool) -> NativeCallback {
+    return Arc::new(move |arguments, _| {
+        let str = get_string_argument(arguments, 0, name)?;
+        let other = get_string_argument(arguments, 1, name)?;
+        Ok(Object::Bool(operation(&str, &other)))
+    });
+}
+
+pub fn repeat(
+    arguments: &Vec<Object>,
+    _: Arc<Mutex<Environment>>,
+) -> Result<Object, NoaTermination> {
+    let str = get_string_argument(arguments, 0, "repeat")?;
+    let count = get_integer_argument(arguments, 1, "repeat")?;
+    if count < 0 {
+        return Err(string_error(
+            "repeat",
+            format!("repeat expects a non-negative count, got {}", count),
+        ));
+    }
+    check_string_len("repeat", str.len().checked_mul(count as usize))?;
+    Ok(Object::String(str.repeat(count as usize)))
+}
+
+fn pad(arguments: &Vec<Object>, function: &str, left: bool) -> Result<Object, NoaTermination> {
+    check_argument_count(arguments, 3, function)?;
+    let str = get_string_argument(arguments, 0, function)?;
+    let width = get_integer_argument(arguments, 1, function)?;
+    let fill = match arguments.get(2) {
+        Some(_) => get_single_char_argument(arguments, 2, function)?,
+        None => ' ',
+    };
+    let len = str.chars().count() as i64;
+    if width <= len {
+        return Ok(Object::String(str));
+    }
+    let count = (width - len) as usize;
+    check_string_len(
+        function,
+        fill.len_utf8()
+            .checked_mul(count)
+            .and_then(|bytes| bytes.checked_add(str.len())),
+    )?;
+    let padding: String = std::iter::repeat_n(fill, count).collect();
+    if left {
+        return Ok(Object::String(padding + &str));
+    }
+    Ok(Object::String(str + &padding))
+}
+
+pub fn pad_left(
+    arguments: &Vec<Object>,
+    _: Arc<Mutex<Environment>>,
+) -> Result<Object, NoaTermination> {
+    pad(arguments, "pad_left", true)
+}
+
+pub fn pad_right(
+    arguments: &Vec<Object>,
+    _: Arc<Mutex<Environment>>,
+) -> Result<Object, NoaTermination> {
+    pad(arguments, "pad_right", false)
+}
+
+pub fn chars(
+    arguments: &Vec<Object>,
+    _: Arc<Mutex<Environment>>,
+) -> Result<Object, NoaTermination> {
+    let str = get_string_argument(arguments, 0, "chars")?;
+    Ok(make_list(
+        str.chars().map(|c| Object::String(c.to_string())).collect(),
+    ))
+}
+
+pub fn ord(arguments: &Vec<Object>, _: Arc<Mutex<Environment>>) -> Result<Object, NoaTermination> {
+    let c = get_single_char_argument(arguments, 0, "ord")?;
+    Ok(Object::Int(c as i64))
+}
+
+pub fn chr(arguments: &Vec<Object>, _: Arc<Mutex<Environment>>) -> Result<Object, NoaTermination> {
+    let code = get_integer_argument(arguments, 0, "chr")?;
+    match u32::try_from(code).ok().and_then(char::from_u32) {
+        Some(c) => Ok(Object::String(c.to_string())),
+        None => Err(string_error(
+            "chr",
+            format!("{} is not a valid Unicode code point", code),
+        )),
+    }
+}
+
+/// A parsed `{index:spec}` placeholder. The spec is `[[fill]align][width][.precision]`.
+struct Placeholder {
+    index: Option<usize>,
+    fill: char,
+    align: Option<char>,
+    width: usize,
+    precision: Option<usize>,
+}
+
+fn parse_placeholder(text: &str) -> Result<Placeholder, String> {
+    let (index, spec) = match text.split_once(':') {
+        Some((index, spec)) => (index, spec),
+        None => (text, ""),
+    };
+    let index = match index {
+        "" => None,
+        index => match index.parse::<usize>() {
+            Ok(index) => Some(index),
+            Err(_) => return Err(format!("Invalid placeholder index \"{}\"", index)),
+        },
+    };
+
+    let mut placeholder = Placeholder {
+        index,
+        fill: ' ',
+        align: None,
+        width: 0,
+        precision: None,
+    };
+    let chars: Vec<char> = spec.chars().collect();
+    let mut i = 0;
+    let is_align = |c: Option<&char>| matches!(c, Some('<' | '>' | '^'));
+    if is_align(chars.get(1)) {
+        placeholder.fill = chars[0];
+        placeholder.align = Some(chars[1]);
+        i = 2;
+    } else if is_align(chars.first()) {
+        placeholder.align = Some(chars[0]);
+        i = 1;
+    }
+    let digits = |i: &mut usize| {
+        let start = *i;
+        while chars.get(*i).is_some_and(|c| c.is_ascii_digit()) {
+            *i += 1;
+        }
+        chars[start..*i].iter().collect::<String>()
+    };
+    let width = digits(&mut i);
+    if !width.is_empty() {
+        placeholder.width = width.parse().map_err(|_| "Width is too large".to_owned())?;
+    }
+    if chars.get(i) == Some(&'.') {
+        i += 1;
+        let precision = digits(&mut i);
+        if precision.is_empty() {
+            return Err("Expected digits after '.' in format spec".to_owned());
+        }
+        placeholder.precision = Some(
+            precision
+                .parse()
+                .map_err(|_| "Precision is too large".to_owned())?,
+        );
+    }
+    if i < chars.len() {
+        return Err(format!("Invalid format spec \"{}\"", spec));
+    }
+    // Padding and float digits are allocated up front, so keep them within what
+    // other natives may build
+    if placeholder
+        .width
+        .saturating_mul(placeholder.fill.len_utf8())
+        > MAX_STRING_LEN
+    {
+        return Err("Width is too large".to_owned());
+    }
+    if placeholder
+        .precision
+        .is_some_and(|precision| precision > MAX_STRING_LEN)
+    {
+        return Err("Precision is too large".to_owned());
+    }
+    return Ok(placeholder);
+}
+
+fn format_value(value: &Object, placeholder: &Placeholder) -> String {
+    let text = match (value, placeholder.precision) {
+        (Object::Number(num), Some(precision)) => format!("{:.*}", precision, num),
+        (Object::Int(num), Some(precision)) => format!("{:.*}", precision, *num as f64),
+        (_, Some(precision)) => value.to_string().chars().take(precision).collect(),
+        (_, None) => value.to_string(),
+    };
+    let len = text.chars().count();
+    if len >= placeholder.width {
+        return text;
+    }
+    let padding = placeholder.width - len;
+    // Numbers line up on the right by default, everything else on the left
+    let align = placeholder.align.unwrap_or(match value {
+        Object::Number(_) | Object::Int(_) => '>',
+        _ => '<',
+    });
+    let (before, after) = match align {
+        '>' => (padding, 0),
+        '^' => (padding / 2, padding - padding / 2),
+        _ => (0, padding),
+    };
+    let fill = |count| std::iter::repeat_n(placeholder.fill, count).collect::<String>();
+    return fill(before) + &text + &fill(after);
+}
+
+/// `format("{} has {:.2}", a, b)`. Placeholders take the next argument, or the one
+/// at an explicit index like `{0}`. `{{` and `}}` are literal braces.
+pub fn format_string(
+    arguments: &Vec<Object>,
+    _: Arc<Mutex<Environment>>,
+) -> Result<Object, NoaTermination> {
+    let template = get_string_argument(arguments, 0, "format")?;
+    let values = &arguments[1..];
+    let mut output = String::new();
+    let mut next = 0;
+    let mut chars = template.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '{' if chars.peek() == Some(&'{') => {
+                chars.next();
+                output.push('{');
+            }
+            '}' if chars.peek() == Some(&'}') => {
+                chars.next();
+                output.push('}');
+            }
+            '}' => {
+                return Err(string_error(
+                    "format",
+                    "Unmatched '}' in format string, use '}}' for a literal brace".to_owned(),
+                ));
+            }
+            '{' => {
+                let mut text = String::new();
+                loop {
+                    match chars.next() {
+                        Some('}') => break,
+                        Some(c) => text.push(c),
+                        None => {
+                            return Err(string_error(
+                                "format",
+                                "Unterminated placeholder in format string".to_owned(),
+                            ));
+                        }
+                    }
+                }
+                let placeholder =
+                    parse_placeholder(&text).map_err(|message| string_error("format", message))?;
+                let index = match placeholder.index {
+                    Some(index) => index,
+                    None => {
+                        next += 1;
+                        next - 1
+                    }
+                };
+                match values.get(index) {
+                    Some(value) => output.push_str(&format_value(value, &placeholder)),
+                    None => {
+                        return Err(string_error(
+                            "format",
+                            format!(
+                                "Placeholder refers to argument {} but only {} were given",
+                                index + 1,
+                                values.len()
+                            ),
+                        ));
+                    }
+                }
+            }
+            c => output.push(c),
+        }
+    }
+    Ok(Object::String(output))
+}
@@ -0,0 +1,70 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+};
+
+use crate::noa::{
+    error::{NoaError, NoaTermination},
+    string::get_string_argument,
+    types::{NativeCallback, Object},
+};
+
+/// Variables set by `set_env`. `None` marks a variable the script removed.
+/// Changing the real process environment is not thread safe, and scripts can't
+/// start processes, so the changes are kept per interpreter instead.
+pub type SharedEnvOverrides = Arc<Mutex<HashMap<String, Option<String>>>>;
+
+fn system_error(function: &str, message: String) -> NoaTermination {
+    return NoaTermination::Error(NoaError {
+        line: 0,
+        location: function.to_owned(),
+        message,
+    });
+}
+
+/// `env(name)` returns the variable's value, or nil when it isn't set.
+pub fn get_env(overrides: SharedEnvOverrides) -> NativeCallback {
+    return Arc::new(move |arguments, _| {
+        let name = get_string_argument(arguments, 0, "env")?;
+        let value = match overrides.lock() {
+            Ok(overrides) => match overrides.get(&name) {
+                Some(value) => value.clone(),
+                None => std::env::var(&name).ok(),
+            },
+            Err(_) => return Err(system_error("env", "Failed to lock environment".to_owned())),
+        };
+        Ok(value.map(Object::String).unwrap_or(Object::Nil))
+    });
+}
+
+/// `set_env(name, value)` sets a variable, or removes it when `value` is nil.
+pub fn set_env(overrides: SharedEnvOverrides) -> NativeCallback {
+    return Arc::new(move |arguments, _| {
+        let name = get_string_argument(arguments, 0, "set_env")?;
+        let value = match &arguments[1] {
+            Object::Nil => None,
+            Object::String(value) => Some(value.clone()),
+            other => {
+                return Err(system_error(
+                    "set_env",
+                    format!(
+                        "set_env expects a string or nil as argument 2, got \"{}\"",
+                        other
+                    ),
+                ));
+            }
+        };
+        match overrides.lock() {
+            Ok(mut overrides) => {
+                overrides.insert(name, value);
+            }
+            Err(_) => {
+                return Err(system_error(
+                    "set_env",
+                    "Failed to lock environment".to_owned(),
+                ));
+            }
+        }
+        Ok(Object::Nil)
+    });
+}
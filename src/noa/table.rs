@@ -0,0 +1,217 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+};
+
+use crate::noa::{
+    environment::Environment,
+    error::{NoaError, NoaTermination},
+    string::get_string_argument,
+    types::{Object, Table},
+};
+
+fn table_error(function: &str, message: String) -> NoaTermination {
+    return NoaTermination::Error(NoaError {
+        line: 0,
+        location: function.to_owned(),
+        message,
+    });
+}
+
+pub fn get_table_argument(
+    arguments: &Vec<Object>,
+    index: usize,
+    function: &str,
+) -> Result<Arc<Mutex<Table>>, NoaTermination> {
+    match arguments.get(index) {
+        Some(Object::Table(table)) => Ok(table.clone()),
+        other => Err(table_error(
+            function,
+            format!(
+                "{} expects a table as argument {}, got \"{}\"",
+                function,
+                index + 1,
+                other.unwrap_or(&Object::Nil)
+            ),
+        )),
+    }
+}
+
+/// Locks the table argument at `index` and hands it to `operation`.
+fn with_table<T>(
+    arguments: &Vec<Object>,
+    index: usize,
+    function: &str,
+    operation: impl FnOnce(&mut Table) -> T,
+) -> Result<T, NoaTermination> {
+    let table = get_table_argument(arguments, index, function)?;
+    match table.lock() {
+        Ok(mut table) => Ok(operation(&mut table)),
+        Err(_) => Err(table_error(function, "Failed to lock table".to_owned())),
+    }
+}
+
+fn make_list(values: Vec<Object>) -> Object {
+    return Object::List(Arc::new(Mutex::new(values)));
+}
+
+pub fn keys(arguments: &Vec<Object>, _: Arc<Mutex<Environment>>) -> Result<Object, NoaTermination> {
+    with_table(arguments, 0, "keys", |table| {
+        make_list(
+            table
+                .values
+                .keys()
+                .map(|key| Object::String(key.clone()))
+                .collect(),
+        )
+    })
+}
+
+pub fn values(
+    arguments: &Vec<Object>,
+    _: Arc<Mutex<Environment>>,
+) -> Result<Object, NoaTermination> {
+    with_table(arguments, 0, "values", |table| {
+        make_list(table.values.values().cloned().collect())
+    })
+}
+
+pub fn entries(
+    arguments: &Vec<Object>,
+    _: Arc<Mutex<Environment>>,
+) -> Result<Object, NoaTermination> {
+    with_table(arguments, 0, "entries", |table| {
+        make_list(
+            table
+                .values
+                .iter()
+                .map(|(key, value)| make_list(vec![Object::String(key.clone()), value.clone()]))
+                .collect(),
+        )
+    })
+}
+
+pub fn has(arguments: &Vec<Object>, _: Arc<Mutex<Environment>>) -> Result<Object, NoaTermination> {
+    let key = get_string_argument(arguments, 1, "has")?;
+    with_table(arguments, 0, "has", |table| Object::Bool(table.has(&key)))
+}
+
+pub fn remove(
+    arguments: &Vec<Object>,
+    _: Arc<Mutex<Environment>>,
+) -> Result<Object, NoaTermination> {
+    let key = get_string_argument(arguments, 1, "remove")?;
+    match with_table(arguments, 0, "remove", |table| table.remove(&key))? {
+        Ok(value) => Ok(value),
+        Err(message) => Err(table_error("remove", message)),
+    }
+}
+
+/// Returns a new table with the entries of both tables, the second one winning on conflicts.
+pub fn merge(
+    arguments: &Vec<Object>,
+    _: Arc<Mutex<Environment>>,
+) -> Result<Object, NoaTermination> {
+    let mut merged = with_table(arguments, 0, "merge", |table| table.clone())?;
+    let other = with_table(arguments, 1, "merge", |table| table.clone())?;
+    merged.frozen = false;
+    for (key, value) in other.values {
+        merged.insert(key, value);
+    }
+    Ok(Object::Table(Arc::new(Mutex::new(merged))))
+}
+
+pub fn copy(arguments: &Vec<Object>, _: Arc<Mutex<Environment>>) -> Result<Object, NoaTermination> {
+    match arguments.first().unwrap() {
+        Object::Table(_) => {
+            let mut table = with_table(arguments, 0, "copy", |table| table.clone())?;
+            table.frozen = false;
+            Ok(Object::Table(Arc::new(Mutex::new(table))))
+        }
+        Object::List(list) => match list.lock() {
+            Ok(list) => Ok(make_list(list.clone())),
+            Err(_) => Err(table_error("copy", "Failed to lock list".to_owned())),
+        },
+        other => Ok(other.clone()),
+    }
+}
+
+/// Copies nested tables and lists too. Shared or cyclic references stay shared in the copy.
+fn deep_copy_object(
+    object: &Object,
+    copies: &mut HashMap<usize, Object>,
+) -> Result<Object, NoaTermination> {
+    match object {
+        Object::Table(table) => {
+            let address = Arc::as_ptr(table) as usize;
+            if let Some(copy) = copies.get(&address) {
+                return Ok(copy.clone());
+            }
+            let source = match table.lock() {
+                Ok(table) => table.clone(),
+                Err(_) => return Err(table_error("deep_copy", "Failed to lock table".to_owned())),
+            };
+            let copy = Arc::new(Mutex::new(Table::new()));
+            copies.insert(address, Object::Table(copy.clone()));
+            for (key, value) in source.values.iter() {
+                let value = deep_copy_object(value, copies)?;
+                match copy.lock() {
+                    Ok(mut copy) => copy.insert(key.clone(), value),
+                    Err(_) => {
+                        return Err(table_error("deep_copy", "Failed to lock table".to_owned()));
+                    }
+                }
+            }
+            Ok(Object::Table(copy))
+        }
+        Object::List(list) => {
+            let address = Arc::as_ptr(list) as usize;
+            if let Some(copy) = copies.get(&address) {
+                return Ok(copy.clone());
+            }
+            let source = match list.lock() {
+                Ok(list) => list.clone(),
+                Err(_) => return Err(table_error("deep_copy", "Failed to lock list".to_owned())),
+            };
+            let copy = Arc::new(Mutex::new(Vec::new()));
+            copies.insert(address, Object::List(copy.clone()));
+            for value in source.iter() {
+                let value = deep_copy_object(value, copies)?;
+                match copy.lock() {
+                    Ok(mut copy) => copy.push(value),
+                    Err(_) => {
+                        return Err(table_error("deep_copy", "Failed to lock list".to_owned()));
+                    }
+                }
+            }
+            Ok(Object::List(copy))
+        }
+        other => Ok(other.clone()),
+    }
+}
+
+pub fn deep_copy(
+    arguments: &Vec<Object>,
+    _: Arc<Mutex<Environment>>,
+) -> Result<Object, NoaTermination> {
+    deep_copy_object(arguments.first().unwrap(), &mut HashMap::new())
+}
+
+/// `freeze(table)` makes the table read-only and returns it. Nested tables aren't
+/// frozen, and `copy` gives a table that can be changed again.
+pub fn freeze(
+    arguments: &Vec<Object>,
+    _: Arc<Mutex<Environment>>,
+) -> Result<Object, NoaTermination> {
+    with_table(arguments, 0, "freeze", |table| table.frozen = true)?;
+    Ok(arguments[0].clone())
+}
+
+pub fn is_frozen(
+    arguments: &Vec<Object>,
+    _: Arc<Mutex<Environment>>,
+) -> Result<Object, NoaTermination> {
+    with_table(arguments, 0, "is_frozen", |table| {
+        Object::Bool(table.frozen)
+    })
+}
@@ -0,0 +1,247 @@
+use std::{
+    fmt::Write,
+    sync::{Arc, Mutex},
+    thread,
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+use crate::noa::{
+    error::{NoaError, NoaTermination},
+    number::get_number_argument,
+    string::get_string_argument,
+    types::{NativeCallback, Number, Object},
+};
+
+/// Source of time for `clock`, `now`, `sleep` and `date`. Hosts can swap in a fake
+/// clock to make scripts that depend on time deterministic.
+pub trait Clock: Send {
+    /// Seconds since an arbitrary fixed point. Never goes backwards.
+    fn monotonic(self: &Self) -> Number;
+    /// Seconds since the unix epoch.
+    fn now(self: &Self) -> Number;
+    fn sleep(self: &mut Self, seconds: Number);
+}
+
+/// Clock backed by the operating system.
+pub struct SystemClock {
+    start: Instant,
+}
+
+impl SystemClock {
+    pub fn new() -> Self {
+        return SystemClock {
+            start: Instant::now(),
+        };
+    }
+}
+
+impl Default for SystemClock {
+    fn default() -> Self {
+        return Self::new();
+    }
+}
+
+impl Clock for SystemClock {
+    fn monotonic(self: &Self) -> Number {
+        return self.start.elapsed().as_secs_f64();
+    }
+    fn now(self: &Self) -> Number {
+        match SystemTime::now().duration_since(UNIX_EPOCH) {
+            Ok(duration) => return duration.as_secs_f64(),
+            Err(error) => return -error.duration().as_secs_f64(),
+        }
+    }
+    fn sleep(self: &mut Self, seconds: Number) {
+        if let Ok(duration) = Duration::try_from_secs_f64(seconds) {
+            thread::sleep(duration);
+        }
+    }
+}
+
+pub type SharedClock = Arc<Mutex<Box<dyn Clock>>>;
+
+fn time_error(function: &str, message: String) -> NoaTermination {
+    return NoaTermination::Error(NoaError {
+        line: 0,
+        location: function.to_owned(),
+        message,
+    });
+}
+
+fn with_clock<T>(
+    clock: &SharedClock,
+    function: &str,
+    operation: impl FnOnce(&mut Box<dyn Clock>) -> T,
+) -> Result<T, NoaTermination> {
+    match clock.lock() {
+        Ok(mut clock) => Ok(operation(&mut clock)),
+        Err(_) => Err(time_error(function, "Failed to lock clock".to_owned())),
+    }
+}
+
+pub fn clock(clock: SharedClock) -> NativeCallback {
+    return Arc::new(move |_, _| {
+        with_clock(&clock, "clock", |clock| Object::Number(clock.monotonic()))
+    });
+}
+
+pub fn now(clock: SharedClock) -> NativeCallback {
+    return Arc::new(move |_, _| with_clock(&clock, "now", |clock| Object::Number(clock.now())));
+}
+
+/// `sleep(ms)` pauses the script for `ms` milliseconds.
+pub fn sleep(clock: SharedClock) -> NativeCallback {
+    return Arc::new(move |arguments, _| {
+        let ms = get_number_argument(arguments, 0, "sleep")?;
+        if !ms.is_finite() || ms < 0.0 {
+            return Err(time_error(
+                "sleep",
+                format!("sleep expects a non-negative duration, got {}", ms),
+            ));
+        }
+        if Duration::try_from_secs_f64(ms / 1000.0).is_err() {
+            return Err(time_error(
+                "sleep",
+                format!("sleep duration {} is too long", ms),
+            ));
+        }
+        with_clock(&clock, "sleep", |clock| clock.sleep(ms / 1000.0))?;
+        Ok(Object::Nil)
+    });
+}
+
+const WEEKDAYS: [&str; 7] = [
+    "Sunday",
+    "Monday",
+    "Tuesday",
+    "Wednesday",
+    "Thursday",
+    "Friday",
+    "Saturday",
+];
+const MONTHS: [&str; 12] = [
+    "January",
+    "February",
+    "March",
+    "April",
+    "May",
+    "June",
+    "July",
+    "August",
+    "September",
+    "October",
+    "November",
+    "December",
+];
+
+/// Converts days since the unix epoch into a (year, month, day) in the proleptic
+/// Gregorian calendar.
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let days = days + 719468;
+    let era = days.div_euclid(146097);
+    let day_of_era = days.rem_euclid(146097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = (day_of_year - (153 * month_index + 2) / 5 + 1) as u32;
+    let month = if month_index < 10 {
+        month_index + 3
+    } else {
+        month_index - 9
+    } as u32;
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+    return (year, month, day);
+}
+
+fn is_leap_year(year: i64) -> bool {
+    return year % 4 == 0 && (year % 100 != 0 || year % 400 == 0);
+}
+
+/// Formats a unix timestamp in UTC using strftime-style specifiers.
+fn format_date(format: &str, timestamp: Number) -> Result<String, String> {
+    let seconds = timestamp.floor() as i64;
+    let days = seconds.div_euclid(86400);
+    let second_of_day = seconds.rem_euclid(86400);
+    let (year, month, day) = civil_from_days(days);
+    let weekday = (days + 4).rem_euclid(7) as usize;
+    let hour = second_of_day / 3600;
+    let minute = second_of_day / 60 % 60;
+    let second = second_of_day % 60;
+    let cumulative_days = [0, 31, 59, 90, 120, 151, 181, 212, 243, 273, 304, 334];
+    let mut day_of_year = cumulative_days[month as usize - 1] + day;
+    if month > 2 && is_leap_year(year) {
+        day_of_year += 1;
+    }
+
+    let mut output = String::new();
+    let mut chars = format.chars();
+    while let Some(c) = chars.next() {
+        if c != '%' {
+            output.push(c);
+            continue;
+        }
+        let _ = match chars.next() {
+            Some('Y') => write!(output, "{}", year),
+            Some('y') => write!(output, "{:02}", year.rem_euclid(100)),
+            Some('m') => write!(output, "{:02}", month),
+            Some('d') => write!(output, "{:02}", day),
+            Some('e') => write!(output, "{:2}", day),
+            Some('H') => write!(output, "{:02}", hour),
+            Some('I') => write!(output, "{:02}", (hour + 11) % 12 + 1),
+            Some('p') => write!(output, "{}", if hour < 12 { "AM" } else { "PM" }),
+            Some('M') => write!(output, "{:02}", minute),
+            Some('S') => write!(output, "{:02}", second),
+            Some('f') => write!(
+                output,
+                "{:03}",
+                ((timestamp - timestamp.floor()) * 1000.0) as i64
+            ),
+            Some('j') => write!(output, "{:03}", day_of_year),
+            Some('a') => write!(output, "{}", &WEEKDAYS[weekday][..3]),
+            Some('A') => write!(output, "{}", WEEKDAYS[weekday]),
+            Some('w') => write!(output, "{}", weekday),
+            Some('b') => write!(output, "{}", &MONTHS[month as usize - 1][..3]),
+            Some('B') => write!(output, "{}", MONTHS[month as usize - 1]),
+            Some('s') => write!(output, "{}", seconds),
+            Some('F') => write!(output, "{}-{:02}-{:02}", year, month, day),
+            Some('T') => write!(output, "{:02}:{:02}:{:02}", hour, minute, second),
+            Some('%') => write!(output, "%"),
+            Some(other) => return Err(format!("Unknown date specifier %{}", other)),
+            None => return Err("Date format ends with a lone %".to_owned()),
+        };
+    }
+    return Ok(output);
+}
+
+/// `date(format, timestamp?)` formats a unix timestamp, or the current time, in UTC.
+pub fn date(clock: SharedClock) -> NativeCallback {
+    return Arc::new(move |arguments, _| {
+        if arguments.len() > 2 {
+            return Err(time_error(
+                "date",
+                format!(
+                    "date expects at most 2 arguments got {} arguments",
+                    arguments.len()
+                ),
+            ));
+        }
+        let format = get_string_argument(arguments, 0, "date")?;
+        let timestamp = match arguments.get(1) {
+            Some(_) => get_number_argument(arguments, 1, "date")?,
+            None => with_clock(&clock, "date", |clock| clock.now())?,
+        };
+        // Whole seconds have to fit an i64: -2^63 is exact as a float, 2^63 is the first
+        // float past i64::MAX
+        if !(-9223372036854775808.0..9223372036854775808.0).contains(&timestamp) {
+            return Err(time_error(
+                "date",
+                format!("date timestamp {} is out of range", timestamp),
+            ));
+        }
+        match format_date(&format, timestamp) {
+            Ok(date) => Ok(Object::String(date)),
+            Err(message) => Err(time_error("date", message)),
+        }
+    });
+}
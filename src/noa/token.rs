@@ -16,8 +16,13 @@ pub enum TokenType {
     Semicolon,
     Slash,
     Star,
+    Percent,
     QuestionMark,
     Colon,
+    Ampersand,
+    Pipe,
+    Caret,
+    Tilde,
 
     // One or two character token
     Bang,
@@ -28,20 +33,45 @@ pub enum TokenType {
     GreaterEqual,
     Less,
     LessEqual,
+    LessLess,
+    GreaterGreater,
+    StarStar,
+    /// `=>`, between a `match` pattern and its body.
+    FatArrow,
+    /// `??`, nil-coalescing.
+    QuestionQuestion,
+    /// `?.`, followed by a name or a call.
+    QuestionDot,
+    /// `?[` written right after an operand. Otherwise, as in `c ?[1] : [2]`, it is a
+    /// ternary.
+    QuestionBracket,
+    PlusPlus,
+    MinusMinus,
+    PlusEqual,
+    MinusEqual,
+    StarEqual,
+    SlashEqual,
+    PercentEqual,
+    /// `~/`, integer division. `//` always starts a comment.
+    TildeSlash,
 
     // Literals
     Identifier,
     String,
+    /// Text of a template string before an embedded `${...}` expression.
+    Interpolation,
     Number,
 
     // Keywords
     And,
     Class,
+    Const,
     Else,
     False,
     Fn,
     For,
     If,
+    In,
     Nil,
     Or,
     Return,
@@ -49,8 +79,10 @@ pub enum TokenType {
     This,
     True,
     Let,
+    Match,
     While,
 
+    #[allow(clippy::upper_case_acronyms)]
     EOF,
 }
 
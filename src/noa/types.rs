@@ -1,35 +1,49 @@
 use std::{
-    collections::HashMap,
-    fmt::{self, Error},
+    cmp::Ordering,
+    fmt,
     sync::{Arc, Mutex},
 };
 
+use indexmap::IndexMap;
+
 use crate::noa::{environment::Environment, error::NoaTermination, statement::Statement};
 
 pub type Number = f64;
 
-#[derive(Debug, Clone)]
+pub type NativeCallback = Arc<
+    dyn Fn(&Vec<Object>, Arc<Mutex<Environment>>) -> Result<Object, NoaTermination> + Send + Sync,
+>;
+
+#[derive(Clone)]
 pub struct Function {
     pub body: Option<Box<Statement>>,
     pub params: Vec<String>,
+    /// Accept more arguments than `params`; natives check the upper bound themselves.
+    pub variadic: bool,
     pub environment: Arc<Mutex<Environment>>,
-    pub callback: Option<
-        fn(
-            arguments: &Vec<Object>,
-            environment: Arc<Mutex<Environment>>,
-        ) -> Result<Object, NoaTermination>,
-    >,
+    pub callback: Option<NativeCallback>,
+}
+
+impl fmt::Debug for Function {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Function")
+            .field("body", &self.body)
+            .field("params", &self.params)
+            .field("variadic", &self.variadic)
+            .field("native", &self.callback.is_some())
+            .finish()
+    }
 }
 
 impl Function {
     pub fn call(self: &Self, arguments: Vec<Object>) -> Result<Object, NoaTermination> {
         let mut environment = Environment::new();
         environment.enclose(self.environment.clone());
-        for (i, arg) in arguments.iter().enumerate() {
-            environment.define(self.params[i].clone(), arg.clone());
+        for (param, arg) in self.params.iter().zip(arguments.iter()) {
+            environment.define(param.clone(), arg.clone());
         }
-        let mut environment = Arc::new(Mutex::new((environment)));
-        match self.callback {
+        let environment = Arc::new(Mutex::new(environment));
+        match &self.callback {
             Some(callback) => {
                 return callback(&arguments, environment);
             }
@@ -55,12 +69,21 @@ impl Function {
     }
 }
 
+/// Keys keep the order they were first inserted in, so iteration and printing are stable.
 #[derive(Debug, Clone)]
 pub struct Table {
-    pub values: HashMap<String, Object>,
+    pub values: IndexMap<String, Object>,
+    /// Set by `freeze`. A frozen table refuses `set_value` and `remove`.
+    pub frozen: bool,
 }
 
 impl Table {
+    pub fn new() -> Self {
+        return Table {
+            values: IndexMap::new(),
+            frozen: false,
+        };
+    }
     pub fn get_value(self: &Self, key: String) -> Object {
         match self.values.get(&key) {
             Some(value) => {
@@ -69,27 +92,96 @@ impl Table {
             None => Object::Nil, // Somehow this is returned
         }
     }
-    pub fn set_value(self: &mut Self, key: String, value: Object) {
+    /// Storing `nil` removes the key, so a key is present exactly when its value isn't `nil`.
+    pub fn set_value(self: &mut Self, key: String, value: Object) -> Result<(), String> {
+        if self.frozen {
+            return Err(format!("Cannot set \"{}\" on a frozen table", key));
+        }
+        self.insert(key, value);
+        return Ok(());
+    }
+    /// Same as `set_value` without the frozen check, for filling in a table being built.
+    pub fn insert(self: &mut Self, key: String, value: Object) {
+        if let Object::Nil = value {
+            self.values.shift_remove(&key);
+            return;
+        }
         self.values.insert(key, value);
     }
+    pub fn has(self: &Self, key: &str) -> bool {
+        return self.values.contains_key(key);
+    }
+    pub fn remove(self: &mut Self, key: &str) -> Result<Object, String> {
+        if self.frozen {
+            return Err(format!("Cannot remove \"{}\" from a frozen table", key));
+        }
+        return Ok(self.values.shift_remove(key).unwrap_or(Object::Nil));
+    }
+}
+
+impl Default for Table {
+    fn default() -> Self {
+        return Self::new();
+    }
 }
 
 #[derive(Debug, Clone)]
 pub enum Object {
+    /// Floating point number.
     Number(Number),
+    /// Integer. Literals without a fraction or exponent are ints, and arithmetic
+    /// between ints stays an int, erroring on overflow.
+    Int(i64),
     String(String),
     Bool(bool),
     Function(Box<Function>),
     Table(Arc<Mutex<Table>>),
+    List(Arc<Mutex<Vec<Object>>>),
     Nil,
 }
 
+/// Orders an int and a float exactly. Converting the int to a float would round ints
+/// above 2^53, making e.g. 2^53 + 1 equal to 2^53 as a float.
+fn compare_int_float(int: i64, float: Number) -> Option<Ordering> {
+    if float.is_nan() {
+        return None;
+    }
+    // Every i64 lies in [-2^63, 2^63)
+    if float >= 9223372036854775808.0 {
+        return Some(Ordering::Less);
+    }
+    if float < -9223372036854775808.0 {
+        return Some(Ordering::Greater);
+    }
+    let whole = float.trunc();
+    match int.cmp(&(whole as i64)) {
+        Ordering::Equal => return 0.0.partial_cmp(&(float - whole)),
+        ordering => return Some(ordering),
+    }
+}
+
 impl Object {
+    /// Name of the object's type, as returned by the `type` native.
+    pub fn type_name(self: &Self) -> &'static str {
+        match self {
+            Object::Number(_) => return "number",
+            Object::Int(_) => return "int",
+            Object::String(_) => return "string",
+            Object::Bool(_) => return "bool",
+            Object::Function(_) => return "function",
+            Object::Table(_) => return "table",
+            Object::List(_) => return "list",
+            Object::Nil => return "nil",
+        }
+    }
     pub fn is_truthy(self: &Self) -> bool {
         match self {
             Object::Number(num) => {
                 return *num == 0.0;
             }
+            Object::Int(num) => {
+                return *num == 0;
+            }
             Object::String(str) => return !str.is_empty(),
             Object::Bool(b) => {
                 return *b;
@@ -99,6 +191,19 @@ impl Object {
             }
             Object::Function(_) => return true,
             Object::Table(_) => return true,
+            Object::List(_) => return true,
+        }
+    }
+    /// Orders two numbers of either kind. `None` when one isn't a number or is NaN.
+    fn compare_numbers(self: &Self, comp: &Object) -> Option<Ordering> {
+        match (self, comp) {
+            (Object::Int(a), Object::Int(b)) => return Some(a.cmp(b)),
+            (Object::Int(a), Object::Number(b)) => return compare_int_float(*a, *b),
+            (Object::Number(a), Object::Int(b)) => {
+                return compare_int_float(*b, *a).map(Ordering::reverse);
+            }
+            (Object::Number(a), Object::Number(b)) => return a.partial_cmp(b),
+            _ => return None,
         }
     }
     pub fn is_equal(self: &Self, comp: &Object) -> bool {
@@ -113,15 +218,10 @@ impl Object {
         if let Object::Nil = comp {
             return false;
         }
+        if let Some(ordering) = self.compare_numbers(comp) {
+            return ordering == Ordering::Equal;
+        }
         match self {
-            Object::Number(num1) => match comp {
-                Object::Number(num2) => {
-                    return num1 == num2;
-                }
-                _ => {
-                    return false;
-                }
-            },
             Object::String(str) => match comp {
                 Object::String(str2) => {
                     return str2 == str;
@@ -146,15 +246,10 @@ impl Object {
         }
     }
     pub fn is_greater(self: &Self, comp: &Object) -> bool {
+        if let Some(ordering) = self.compare_numbers(comp) {
+            return ordering == Ordering::Greater;
+        }
         match self {
-            Object::Number(num1) => match comp {
-                Object::Number(num2) => {
-                    return num1 > num2;
-                }
-                _ => {
-                    return false;
-                }
-            },
             Object::String(str) => match comp {
                 Object::String(str2) => {
                     return str2 > str;
@@ -179,15 +274,10 @@ impl Object {
         }
     }
     pub fn is_less(self: &Self, comp: &Object) -> bool {
+        if let Some(ordering) = self.compare_numbers(comp) {
+            return ordering == Ordering::Less;
+        }
         match self {
-            Object::Number(num1) => match comp {
-                Object::Number(num2) => {
-                    return num1 < num2;
-                }
-                _ => {
-                    return false;
-                }
-            },
             Object::String(str) => match comp {
                 Object::String(str2) => {
                     return str2 < str;
@@ -213,27 +303,141 @@ impl Object {
     }
 }
 
+/// Quotes a string the way it would be written in a script.
+pub fn quote_string(str: &str) -> String {
+    let mut quoted = String::from("\"");
+    let mut chars = str.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '$' if chars.peek() == Some(&'{') => quoted.push_str("\\$"),
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            '\r' => quoted.push_str("\\r"),
+            '\t' => quoted.push_str("\\t"),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    return quoted;
+}
+
+/// Writes into a string and fails once the output would grow past `limit` bytes.
+struct LimitedWriter {
+    output: String,
+    limit: usize,
+}
+
+impl fmt::Write for LimitedWriter {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        if self.output.len() + s.len() > self.limit {
+            return Err(fmt::Error);
+        }
+        self.output.push_str(s);
+        Ok(())
+    }
+}
+
+/// Writes a line break followed by `width` spaces, without building the indentation first.
+fn write_indent(f: &mut dyn fmt::Write, width: usize) -> fmt::Result {
+    const SPACES: &str = "                                                                ";
+    f.write_char('\n')?;
+    let mut remaining = width;
+    while remaining > 0 {
+        let chunk = remaining.min(SPACES.len());
+        f.write_str(&SPACES[..chunk])?;
+        remaining -= chunk;
+    }
+    Ok(())
+}
+
+impl Object {
+    /// Writes the object as a Noa literal. `indent` spreads tables and lists over several lines.
+    /// Tables and lists that are already being written are printed as `<cycle>`.
+    fn write_literal(
+        self: &Self,
+        f: &mut dyn fmt::Write,
+        indent: Option<usize>,
+        depth: usize,
+        visiting: &mut Vec<usize>,
+    ) -> fmt::Result {
+        let (open, close, address) = match self {
+            Object::String(s) => return write!(f, "{}", quote_string(s)),
+            Object::Table(table) => ("{", "}", Arc::as_ptr(table) as *const () as usize),
+            Object::List(list) => ("[", "]", Arc::as_ptr(list) as *const () as usize),
+            _ => return write!(f, "{}", self),
+        };
+        if visiting.contains(&address) {
+            return write!(f, "<cycle>");
+        }
+
+        let entries: Vec<(Option<String>, Object)> = match self {
+            Object::Table(table) => match table.lock() {
+                Ok(table) => table
+                    .values
+                    .iter()
+                    .map(|(key, value)| (Some(key.clone()), value.clone()))
+                    .collect(),
+                Err(_) => return Err(fmt::Error),
+            },
+            Object::List(list) => match list.lock() {
+                Ok(list) => list.iter().map(|value| (None, value.clone())).collect(),
+                Err(_) => return Err(fmt::Error),
+            },
+            _ => Vec::new(),
+        };
+        if entries.is_empty() {
+            return write!(f, "{}{}", open, close);
+        }
+
+        visiting.push(address);
+        write!(f, "{}", open)?;
+        for (i, (key, value)) in entries.iter().enumerate() {
+            match indent {
+                Some(indent) => write_indent(f, indent.checked_mul(depth + 1).ok_or(fmt::Error)?)?,
+                None if i > 0 => write!(f, " ")?,
+                None => {}
+            }
+            if let Some(key) = key {
+                write!(f, "{}: ", quote_string(key))?;
+            }
+            value.write_literal(f, indent, depth + 1, visiting)?;
+            if indent.is_some() || i + 1 < entries.len() {
+                write!(f, ",")?;
+            }
+        }
+        if let Some(indent) = indent {
+            write_indent(f, indent.checked_mul(depth).ok_or(fmt::Error)?)?;
+        }
+        write!(f, "{}", close)?;
+        visiting.pop();
+        Ok(())
+    }
+
+    /// Renders tables and lists as multi-line literals indented by `indent` spaces per level.
+    /// Gives `None` when the result would be longer than `limit` bytes.
+    pub fn pretty(self: &Self, indent: usize, limit: usize) -> Option<String> {
+        let mut writer = LimitedWriter {
+            output: String::new(),
+            limit,
+        };
+        match self.write_literal(&mut writer, Some(indent), 0, &mut Vec::new()) {
+            Ok(_) => Some(writer.output),
+            Err(_) => None,
+        }
+    }
+}
+
 impl fmt::Display for Object {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
             Object::Number(i) => write!(f, "{}", i),
+            Object::Int(i) => write!(f, "{}", i),
             Object::String(s) => write!(f, "{}", s),
             Object::Bool(b) => write!(f, "{}", b),
             Object::Nil => write!(f, "nil"),
             Object::Function(_) => write!(f, "[Function]"),
-            Object::Table(table) => {
-                write!(f, "{{")?;
-                match table.lock() {
-                    Ok(mutex) => {
-                        for (key, val) in mutex.values.iter() {
-                            write!(f, "{}:{},", key, val.to_string())?;
-                        }
-                    }
-                    Err(_) => return Err(std::fmt::Error),
-                }
-                write!(f, "}}")?;
-                Ok(())
-            }
+            Object::Table(_) | Object::List(_) => self.write_literal(f, None, 0, &mut Vec::new()),
         }
     }
 }
@@ -0,0 +1,683 @@
+use std::{
+    cell::RefCell,
+    fmt,
+    sync::{Arc, Mutex},
+};
+
+use serde::{
+    Deserialize, Serialize,
+    de::{self, DeserializeOwned, IntoDeserializer, Visitor},
+    forward_to_deserialize_any,
+    ser::{self, SerializeMap, SerializeSeq},
+};
+
+use crate::noa::types::{Object, Table};
+
+/// Error raised while converting between Rust values and [`Object`]s.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ValueError(String);
+
+impl fmt::Display for ValueError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl std::error::Error for ValueError {}
+
+impl ser::Error for ValueError {
+    fn custom<T: fmt::Display>(msg: T) -> Self {
+        return ValueError(msg.to_string());
+    }
+}
+
+impl de::Error for ValueError {
+    fn custom<T: fmt::Display>(msg: T) -> Self {
+        return ValueError(msg.to_string());
+    }
+}
+
+/// Converts any `Serialize` value into a Noa object. Structs and maps become tables,
+/// sequences become lists and `None`/`()` become `nil`.
+pub fn to_value<T: Serialize + ?Sized>(value: &T) -> Result<Object, ValueError> {
+    return value.serialize(ValueSerializer);
+}
+
+/// Converts a Noa object into any `Deserialize` value.
+pub fn from_value<T: DeserializeOwned>(object: Object) -> Result<T, ValueError> {
+    return T::deserialize(object);
+}
+
+fn make_table(table: Table) -> Object {
+    return Object::Table(Arc::new(Mutex::new(table)));
+}
+
+fn make_list(values: Vec<Object>) -> Object {
+    return Object::List(Arc::new(Mutex::new(values)));
+}
+
+thread_local! {
+    // Tables and lists currently being converted, to turn cycles into errors
+    static VISITING: RefCell<Vec<usize>> = const { RefCell::new(Vec::new()) };
+}
+
+/// Runs `convert` with `address` marked as in progress, failing with `cyclic` if it
+/// already is.
+fn guarded<T, E>(
+    address: usize,
+    cyclic: impl FnOnce() -> E,
+    convert: impl FnOnce() -> Result<T, E>,
+) -> Result<T, E> {
+    let is_cyclic = VISITING.with(|visiting| {
+        let mut visiting = visiting.borrow_mut();
+        if visiting.contains(&address) {
+            return true;
+        }
+        visiting.push(address);
+        return false;
+    });
+    if is_cyclic {
+        return Err(cyclic());
+    }
+    let result = convert();
+    VISITING.with(|visiting| visiting.borrow_mut().pop());
+    return result;
+}
+
+/// Runs `serialize` with `address` marked as in progress, failing if it already is.
+fn serialize_guarded<S: ser::Serializer>(
+    address: usize,
+    serialize: impl FnOnce() -> Result<S::Ok, S::Error>,
+) -> Result<S::Ok, S::Error> {
+    return guarded(
+        address,
+        || ser::Error::custom("cannot serialize a table or list that contains itself"),
+        serialize,
+    );
+}
+
+/// Runs `deserialize` with `address` marked as in progress, failing if it already is.
+fn deserialize_guarded<T>(
+    address: usize,
+    deserialize: impl FnOnce() -> Result<T, ValueError>,
+) -> Result<T, ValueError> {
+    return guarded(
+        address,
+        || ValueError("cannot deserialize a table or list that contains itself".to_owned()),
+        deserialize,
+    );
+}
+
+impl Serialize for Object {
+    fn serialize<S: ser::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            Object::Nil => serializer.serialize_unit(),
+            Object::Bool(b) => serializer.serialize_bool(*b),
+            Object::Number(num) => serializer.serialize_f64(*num),
+            Object::Int(num) => serializer.serialize_i64(*num),
+            Object::String(str) => serializer.serialize_str(str),
+            Object::Function(_) => Err(ser::Error::custom("cannot serialize a function")),
+            Object::List(list) => {
+                let values = match list.lock() {
+                    Ok(list) => list.clone(),
+                    Err(_) => return Err(ser::Error::custom("failed to lock list")),
+                };
+                serialize_guarded::<S>(Arc::as_ptr(list) as *const () as usize, || {
+                    let mut seq = serializer.serialize_seq(Some(values.len()))?;
+                    for value in values.iter() {
+                        seq.serialize_element(value)?;
+                    }
+                    seq.end()
+                })
+            }
+            Object::Table(table) => {
+                let values = match table.lock() {
+                    Ok(table) => table.values.clone(),
+                    Err(_) => return Err(ser::Error::custom("failed to lock table")),
+                };
+                serialize_guarded::<S>(Arc::as_ptr(table) as *const () as usize, || {
+                    let mut map = serializer.serialize_map(Some(values.len()))?;
+                    for (key, value) in values.iter() {
+                        map.serialize_entry(key, value)?;
+                    }
+                    map.end()
+                })
+            }
+        }
+    }
+}
+
+/// Serializer whose output is an [`Object`].
+struct ValueSerializer;
+
+struct SeqSerializer {
+    values: Vec<Object>,
+    /// Set for tuple variants, which are wrapped as `{ variant: [...] }`.
+    variant: Option<&'static str>,
+}
+
+struct MapSerializer {
+    table: Table,
+    next_key: Option<String>,
+    /// Set for struct variants, which are wrapped as `{ variant: { ... } }`.
+    variant: Option<&'static str>,
+}
+
+fn wrap_variant(variant: Option<&'static str>, object: Object) -> Object {
+    match variant {
+        Some(variant) => {
+            let mut table = Table::new();
+            table.insert(variant.to_owned(), object);
+            return make_table(table);
+        }
+        None => return object,
+    }
+}
+
+impl ser::Serializer for ValueSerializer {
+    type Ok = Object;
+    type Error = ValueError;
+    type SerializeSeq = SeqSerializer;
+    type SerializeTuple = SeqSerializer;
+    type SerializeTupleStruct = SeqSerializer;
+    type SerializeTupleVariant = SeqSerializer;
+    type SerializeMap = MapSerializer;
+    type SerializeStruct = MapSerializer;
+    type SerializeStructVariant = MapSerializer;
+
+    fn serialize_bool(self, v: bool) -> Result<Object, ValueError> {
+        Ok(Object::Bool(v))
+    }
+    fn serialize_i8(self, v: i8) -> Result<Object, ValueError> {
+        self.serialize_i64(v as i64)
+    }
+    fn serialize_i16(self, v: i16) -> Result<Object, ValueError> {
+        self.serialize_i64(v as i64)
+    }
+    fn serialize_i32(self, v: i32) -> Result<Object, ValueError> {
+        self.serialize_i64(v as i64)
+    }
+    fn serialize_i64(self, v: i64) -> Result<Object, ValueError> {
+        Ok(Object::Int(v))
+    }
+    fn serialize_u8(self, v: u8) -> Result<Object, ValueError> {
+        self.serialize_i64(v as i64)
+    }
+    fn serialize_u16(self, v: u16) -> Result<Object, ValueError> {
+        self.serialize_i64(v as i64)
+    }
+    fn serialize_u32(self, v: u32) -> Result<Object, ValueError> {
+        self.serialize_i64(v as i64)
+    }
+    /// Values too large for an int become floats.
+    fn serialize_u64(self, v: u64) -> Result<Object, ValueError> {
+        match i64::try_from(v) {
+            Ok(v) => Ok(Object::Int(v)),
+            Err(_) => Ok(Object::Number(v as f64)),
+        }
+    }
+    fn serialize_f32(self, v: f32) -> Result<Object, ValueError> {
+        self.serialize_f64(v as f64)
+    }
+    fn serialize_f64(self, v: f64) -> Result<Object, ValueError> {
+        Ok(Object::Number(v))
+    }
+    fn serialize_char(self, v: char) -> Result<Object, ValueError> {
+        Ok(Object::String(v.to_string()))
+    }
+    fn serialize_str(self, v: &str) -> Result<Object, ValueError> {
+        Ok(Object::String(v.to_owned()))
+    }
+    fn serialize_bytes(self, v: &[u8]) -> Result<Object, ValueError> {
+        Ok(make_list(
+            v.iter().map(|byte| Object::Int(*byte as i64)).collect(),
+        ))
+    }
+    fn serialize_none(self) -> Result<Object, ValueError> {
+        Ok(Object::Nil)
+    }
+    fn serialize_some<T: Serialize + ?Sized>(self, value: &T) -> Result<Object, ValueError> {
+        value.serialize(self)
+    }
+    fn serialize_unit(self) -> Result<Object, ValueError> {
+        Ok(Object::Nil)
+    }
+    fn serialize_unit_struct(self, _: &'static str) -> Result<Object, ValueError> {
+        Ok(Object::Nil)
+    }
+    fn serialize_unit_variant(
+        self,
+        _: &'static str,
+        _: u32,
+        variant: &'static str,
+    ) -> Result<Object, ValueError> {
+        Ok(Object::String(variant.to_owned()))
+    }
+    fn serialize_newtype_struct<T: Serialize + ?Sized>(
+        self,
+        _: &'static str,
+        value: &T,
+    ) -> Result<Object, ValueError> {
+        value.serialize(self)
+    }
+    fn serialize_newtype_variant<T: Serialize + ?Sized>(
+        self,
+        _: &'static str,
+        _: u32,
+        variant: &'static str,
+        value: &T,
+    ) -> Result<Object, ValueError> {
+        Ok(wrap_variant(Some(variant), value.serialize(self)?))
+    }
+    fn serialize_seq(self, len: Option<usize>) -> Result<SeqSerializer, ValueError> {
+        Ok(SeqSerializer {
+            values: Vec::with_capacity(len.unwrap_or(0)),
+            variant: None,
+        })
+    }
+    fn serialize_tuple(self, len: usize) -> Result<SeqSerializer, ValueError> {
+        self.serialize_seq(Some(len))
+    }
+    fn serialize_tuple_struct(
+        self,
+        _: &'static str,
+        len: usize,
+    ) -> Result<SeqSerializer, ValueError> {
+        self.serialize_seq(Some(len))
+    }
+    fn serialize_tuple_variant(
+        self,
+        _: &'static str,
+        _: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> Result<SeqSerializer, ValueError> {
+        Ok(SeqSerializer {
+            values: Vec::with_capacity(len),
+            variant: Some(variant),
+        })
+    }
+    fn serialize_map(self, _: Option<usize>) -> Result<MapSerializer, ValueError> {
+        Ok(MapSerializer {
+            table: Table::new(),
+            next_key: None,
+            variant: None,
+        })
+    }
+    fn serialize_struct(self, _: &'static str, len: usize) -> Result<MapSerializer, ValueError> {
+        self.serialize_map(Some(len))
+    }
+    fn serialize_struct_variant(
+        self,
+        _: &'static str,
+        _: u32,
+        variant: &'static str,
+        _: usize,
+    ) -> Result<MapSerializer, ValueError> {
+        Ok(MapSerializer {
+            table: Table::new(),
+            next_key: None,
+            variant: Some(variant),
+        })
+    }
+}
+
+impl ser::SerializeSeq for SeqSerializer {
+    type Ok = Object;
+    type Error = ValueError;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), ValueError> {
+        self.values.push(value.serialize(ValueSerializer)?);
+        Ok(())
+    }
+    fn end(self) -> Result<Object, ValueError> {
+        Ok(wrap_variant(self.variant, make_list(self.values)))
+    }
+}
+
+impl ser::SerializeTuple for SeqSerializer {
+    type Ok = Object;
+    type Error = ValueError;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), ValueError> {
+        ser::SerializeSeq::serialize_element(self, value)
+    }
+    fn end(self) -> Result<Object, ValueError> {
+        ser::SerializeSeq::end(self)
+    }
+}
+
+impl ser::SerializeTupleStruct for SeqSerializer {
+    type Ok = Object;
+    type Error = ValueError;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), ValueError> {
+        ser::SerializeSeq::serialize_element(self, value)
+    }
+    fn end(self) -> Result<Object, ValueError> {
+        ser::SerializeSeq::end(self)
+    }
+}
+
+impl ser::SerializeTupleVariant for SeqSerializer {
+    type Ok = Object;
+    type Error = ValueError;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), ValueError> {
+        ser::SerializeSeq::serialize_element(self, value)
+    }
+    fn end(self) -> Result<Object, ValueError> {
+        ser::SerializeSeq::end(self)
+    }
+}
+
+/// Table keys are strings; numbers, bools and chars used as map keys are stringified.
+fn key_to_string(key: Object) -> Result<String, ValueError> {
+    match key {
+        Object::String(key) => Ok(key),
+        Object::Number(_) | Object::Int(_) | Object::Bool(_) => Ok(key.to_string()),
+        other => Err(ValueError(format!(
+            "table keys must be strings, got {}",
+            other
+        ))),
+    }
+}
+
+impl ser::SerializeMap for MapSerializer {
+    type Ok = Object;
+    type Error = ValueError;
+
+    fn serialize_key<T: Serialize + ?Sized>(&mut self, key: &T) -> Result<(), ValueError> {
+        self.next_key = Some(key_to_string(key.serialize(ValueSerializer)?)?);
+        Ok(())
+    }
+    fn serialize_value<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), ValueError> {
+        match self.next_key.take() {
+            Some(key) => {
+                self.table.insert(key, value.serialize(ValueSerializer)?);
+                Ok(())
+            }
+            None => Err(ValueError(
+                "serialize_value called before serialize_key".to_owned(),
+            )),
+        }
+    }
+    fn end(self) -> Result<Object, ValueError> {
+        Ok(wrap_variant(self.variant, make_table(self.table)))
+    }
+}
+
+impl ser::SerializeStruct for MapSerializer {
+    type Ok = Object;
+    type Error = ValueError;
+
+    fn serialize_field<T: Serialize + ?Sized>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<(), ValueError> {
+        self.table
+            .insert(key.to_owned(), value.serialize(ValueSerializer)?);
+        Ok(())
+    }
+    fn end(self) -> Result<Object, ValueError> {
+        ser::SerializeMap::end(self)
+    }
+}
+
+impl ser::SerializeStructVariant for MapSerializer {
+    type Ok = Object;
+    type Error = ValueError;
+
+    fn serialize_field<T: Serialize + ?Sized>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<(), ValueError> {
+        ser::SerializeStruct::serialize_field(self, key, value)
+    }
+    fn end(self) -> Result<Object, ValueError> {
+        ser::SerializeMap::end(self)
+    }
+}
+
+/// Table keys are always strings; this parses them back when a map is keyed by numbers or bools.
+struct KeyDeserializer(String);
+
+impl KeyDeserializer {
+    fn parse<T: std::str::FromStr>(self: &Self, expected: &str) -> Result<T, ValueError> {
+        match self.0.parse::<T>() {
+            Ok(value) => Ok(value),
+            Err(_) => Err(ValueError(format!(
+                "invalid table key \"{}\", expected {}",
+                self.0, expected
+            ))),
+        }
+    }
+}
+
+impl<'de> IntoDeserializer<'de, ValueError> for KeyDeserializer {
+    type Deserializer = KeyDeserializer;
+
+    fn into_deserializer(self) -> KeyDeserializer {
+        return self;
+    }
+}
+
+macro_rules! deserialize_parsed_key {
+    ($($method:ident => $visit:ident($ty:ty)),* $(,)?) => {
+        $(
+            fn $method<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, ValueError> {
+                visitor.$visit(self.parse::<$ty>(stringify!($ty))?)
+            }
+        )*
+    };
+}
+
+impl<'de> de::Deserializer<'de> for KeyDeserializer {
+    type Error = ValueError;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, ValueError> {
+        visitor.visit_string(self.0)
+    }
+
+    deserialize_parsed_key! {
+        deserialize_bool => visit_bool(bool),
+        deserialize_i8 => visit_i8(i8),
+        deserialize_i16 => visit_i16(i16),
+        deserialize_i32 => visit_i32(i32),
+        deserialize_i64 => visit_i64(i64),
+        deserialize_i128 => visit_i128(i128),
+        deserialize_u8 => visit_u8(u8),
+        deserialize_u16 => visit_u16(u16),
+        deserialize_u32 => visit_u32(u32),
+        deserialize_u64 => visit_u64(u64),
+        deserialize_u128 => visit_u128(u128),
+        deserialize_f32 => visit_f32(f32),
+        deserialize_f64 => visit_f64(f64),
+        deserialize_char => visit_char(char),
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, ValueError> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        _: &'static str,
+        _: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, ValueError> {
+        visitor.visit_enum(self.0.into_deserializer())
+    }
+
+    forward_to_deserialize_any! {
+        str string bytes byte_buf option unit unit_struct seq tuple tuple_struct map struct
+        identifier ignored_any
+    }
+}
+
+impl<'de> IntoDeserializer<'de, ValueError> for Object {
+    type Deserializer = Object;
+
+    fn into_deserializer(self) -> Object {
+        return self;
+    }
+}
+
+impl<'de> de::Deserializer<'de> for Object {
+    type Error = ValueError;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, ValueError> {
+        match self {
+            Object::Nil => visitor.visit_unit(),
+            Object::Bool(b) => visitor.visit_bool(b),
+            Object::Int(num) => visitor.visit_i64(num),
+            // Whole numbers are offered as integers so they deserialize into integer fields
+            Object::Number(num) if num.fract() == 0.0 && num.abs() < 9007199254740992.0 => {
+                visitor.visit_i64(num as i64)
+            }
+            Object::Number(num) => visitor.visit_f64(num),
+            Object::String(str) => visitor.visit_string(str),
+            Object::Function(_) => Err(ValueError("cannot deserialize a function".to_owned())),
+            Object::List(list) => {
+                let values = match list.lock() {
+                    Ok(list) => list.clone(),
+                    Err(_) => return Err(ValueError("failed to lock list".to_owned())),
+                };
+                deserialize_guarded(Arc::as_ptr(&list) as *const () as usize, || {
+                    visitor.visit_seq(de::value::SeqDeserializer::new(values.into_iter()))
+                })
+            }
+            Object::Table(table) => {
+                let values = match table.lock() {
+                    Ok(table) => table.values.clone(),
+                    Err(_) => return Err(ValueError("failed to lock table".to_owned())),
+                };
+                deserialize_guarded(Arc::as_ptr(&table) as *const () as usize, || {
+                    visitor.visit_map(de::value::MapDeserializer::new(
+                        values
+                            .into_iter()
+                            .map(|(key, value)| (KeyDeserializer(key), value)),
+                    ))
+                })
+            }
+        }
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, ValueError> {
+        match self {
+            Object::Nil => visitor.visit_none(),
+            other => visitor.visit_some(other),
+        }
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, ValueError> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    /// Unit variants are strings, other variants are `{ variant: value }` tables.
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        _: &'static str,
+        _: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, ValueError> {
+        match self {
+            Object::String(variant) => visitor.visit_enum(variant.into_deserializer()),
+            Object::Table(table) => {
+                let values = match table.lock() {
+                    Ok(table) => table.values.clone(),
+                    Err(_) => return Err(ValueError("failed to lock table".to_owned())),
+                };
+                if values.len() != 1 {
+                    return Err(ValueError(
+                        "enum variants must be a table with exactly one key".to_owned(),
+                    ));
+                }
+                let (variant, value) = values.into_iter().next().unwrap();
+                visitor.visit_enum(de::value::MapAccessDeserializer::new(
+                    de::value::MapDeserializer::new(std::iter::once((variant, value))),
+                ))
+            }
+            other => Err(ValueError(format!(
+                "expected a string or table for an enum, got {}",
+                other
+            ))),
+        }
+    }
+
+    forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        bytes byte_buf unit unit_struct seq tuple tuple_struct map struct
+        identifier ignored_any
+    }
+}
+
+impl<'de> Deserialize<'de> for Object {
+    fn deserialize<D: de::Deserializer<'de>>(deserializer: D) -> Result<Object, D::Error> {
+        struct ObjectVisitor;
+
+        impl<'de> Visitor<'de> for ObjectVisitor {
+            type Value = Object;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                write!(f, "a value representable in Noa")
+            }
+            fn visit_bool<E>(self, v: bool) -> Result<Object, E> {
+                Ok(Object::Bool(v))
+            }
+            fn visit_i64<E>(self, v: i64) -> Result<Object, E> {
+                Ok(Object::Int(v))
+            }
+            fn visit_u64<E>(self, v: u64) -> Result<Object, E> {
+                match i64::try_from(v) {
+                    Ok(v) => Ok(Object::Int(v)),
+                    Err(_) => Ok(Object::Number(v as f64)),
+                }
+            }
+            fn visit_f64<E>(self, v: f64) -> Result<Object, E> {
+                Ok(Object::Number(v))
+            }
+            fn visit_str<E>(self, v: &str) -> Result<Object, E> {
+                Ok(Object::String(v.to_owned()))
+            }
+            fn visit_string<E>(self, v: String) -> Result<Object, E> {
+                Ok(Object::String(v))
+            }
+            fn visit_unit<E>(self) -> Result<Object, E> {
+                Ok(Object::Nil)
+            }
+            fn visit_none<E>(self) -> Result<Object, E> {
+                Ok(Object::Nil)
+            }
+            fn visit_some<D: de::Deserializer<'de>>(self, d: D) -> Result<Object, D::Error> {
+                Object::deserialize(d)
+            }
+            fn visit_seq<A: de::SeqAccess<'de>>(self, mut seq: A) -> Result<Object, A::Error> {
+                let mut values = Vec::new();
+                while let Some(value) = seq.next_element::<Object>()? {
+                    values.push(value);
+                }
+                Ok(make_list(values))
+            }
+            fn visit_map<A: de::MapAccess<'de>>(self, mut map: A) -> Result<Object, A::Error> {
+                let mut table = Table::new();
+                while let Some((key, value)) = map.next_entry::<String, Object>()? {
+                    table.insert(key, value);
+                }
+                Ok(make_table(table))
+            }
+        }
+
+        deserializer.deserialize_any(ObjectVisitor)
+    }
+}
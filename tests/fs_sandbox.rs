@@ -0,0 +1,88 @@
+#![cfg(unix)]
+
+use std::{fs, os::unix::fs::symlink, path::PathBuf};
+
+use noa::noa::Noa;
+
+fn temp_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("noa-{}-{}", name, std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+fn sandboxed(root: &PathBuf) -> Noa {
+    let mut noa = Noa::new();
+    noa.set_fs_root(root);
+    noa.load_libray();
+    noa
+}
+
+#[test]
+fn dangling_symlink_cannot_escape_root() {
+    let root = temp_dir("root");
+    let outside = temp_dir("outside");
+    let target = outside.join("pwned");
+    symlink(&target, root.join("evil")).unwrap();
+
+    let mut noa = sandboxed(&root);
+    assert!(noa.run("write_file(\"evil\", \"x\");".to_owned()).is_err());
+    assert!(noa.run("append_file(\"evil\", \"x\");".to_owned()).is_err());
+    assert!(!target.exists());
+
+    fs::remove_dir_all(&root).unwrap();
+    fs::remove_dir_all(&outside).unwrap();
+}
+
+#[test]
+fn symlinked_directory_cannot_escape_root() {
+    let root = temp_dir("dir-root");
+    let outside = temp_dir("dir-outside");
+    symlink(&outside, root.join("out")).unwrap();
+
+    let mut noa = sandboxed(&root);
+    assert!(
+        noa.run("write_file(\"out/new\", \"x\");".to_owned())
+            .is_err()
+    );
+    assert!(!outside.join("new").exists());
+
+    fs::remove_dir_all(&root).unwrap();
+    fs::remove_dir_all(&outside).unwrap();
+}
+
+#[test]
+fn symlink_inside_root_is_followed() {
+    let root = temp_dir("inside-root");
+    fs::write(root.join("real.txt"), "hello").unwrap();
+    symlink(root.join("real.txt"), root.join("link.txt")).unwrap();
+
+    let mut noa = sandboxed(&root);
+    assert!(
+        noa.run("append_file(\"link.txt\", \"!\");".to_owned())
+            .is_ok()
+    );
+    assert_eq!(fs::read_to_string(root.join("real.txt")).unwrap(), "hello!");
+
+    fs::remove_dir_all(&root).unwrap();
+}
+
+#[test]
+fn root_cannot_be_removed() {
+    let root = temp_dir("remove-root");
+
+    // The root is empty, so removing it would succeed if it weren't refused
+    let mut noa = sandboxed(&root);
+    for path in ["", ".", "./", "sub/.."] {
+        let script = format!("remove_file(\"{}\");", path);
+        assert!(
+            noa.run(script).is_err(),
+            "removed the root through {:?}",
+            path
+        );
+    }
+    assert!(root.is_dir());
+    assert!(noa.run("list_dir(\".\");".to_owned()).is_ok());
+
+    fs::remove_dir_all(&root).unwrap();
+}
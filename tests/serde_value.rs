@@ -0,0 +1,88 @@
+#![cfg(feature = "serde")]
+
+use std::fmt::Debug;
+
+use noa::noa::Object;
+use noa::{from_value, to_value};
+use serde::{Deserialize, Serialize, Serializer, de::DeserializeOwned};
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+struct Config {
+    name: String,
+    port: u16,
+    ratio: f64,
+    debug: bool,
+    tags: Vec<String>,
+    timeout: Option<i64>,
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+enum Shape {
+    Empty,
+    Circle(f64),
+    Point(i32, i32),
+    Rect { width: u32, height: u32 },
+}
+
+fn round_trip<T: Serialize + DeserializeOwned + PartialEq + Debug>(value: T) {
+    let object = to_value(&value).unwrap();
+    assert_eq!(from_value::<T>(object).unwrap(), value);
+}
+
+#[test]
+fn struct_round_trips() {
+    round_trip(Config {
+        name: "server".to_owned(),
+        port: 8080,
+        ratio: 0.5,
+        debug: true,
+        tags: vec!["a".to_owned(), "b".to_owned()],
+        timeout: Some(30),
+    });
+}
+
+#[test]
+fn enum_round_trips() {
+    round_trip(Shape::Empty);
+    round_trip(Shape::Circle(1.5));
+    round_trip(Shape::Point(-1, 2));
+    round_trip(Shape::Rect {
+        width: 3,
+        height: 4,
+    });
+}
+
+#[test]
+fn option_round_trips() {
+    round_trip(Some(7));
+    round_trip(None::<i64>);
+    assert!(matches!(to_value(&None::<i64>).unwrap(), Object::Nil));
+}
+
+#[test]
+fn vec_round_trips() {
+    round_trip(vec![1, 2, 3]);
+    round_trip(Vec::<String>::new());
+    round_trip(vec![vec![1.5], vec![]]);
+}
+
+struct Bytes(&'static [u8]);
+
+impl Serialize for Bytes {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_bytes(self.0)
+    }
+}
+
+#[test]
+fn bytes_become_ints() {
+    let object = to_value(&Bytes(&[0, 255])).unwrap();
+    let Object::List(list) = object else {
+        panic!("expected a list, got {}", object);
+    };
+    let list = list.lock().unwrap();
+    assert!(matches!(
+        list.as_slice(),
+        [Object::Int(0), Object::Int(255)]
+    ));
+}